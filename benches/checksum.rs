@@ -0,0 +1,31 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use good_game_rusty_pong::{game_state::GameState, helpers::fletcher16};
+
+fn played_state() -> GameState {
+    let mut state = GameState::new();
+    state.left_paddle.score = 3;
+    state.right_paddle.pos = 120;
+    state.ball.pos_x = 42;
+    state.ball.pos_y = 77;
+    state
+}
+
+fn checksum(c: &mut Criterion) {
+    let state = played_state();
+    let mut group = c.benchmark_group("checksum");
+
+    group.bench_function("fletcher16 over bincode", |b| {
+        b.iter(|| {
+            let buffer = bincode::serialize(black_box(&state)).unwrap();
+            fletcher16(&buffer) as u128
+        })
+    });
+    group.bench_function("state hasher in place", |b| {
+        b.iter(|| black_box(&state).checksum() as u128)
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, checksum);
+criterion_main!(benches);
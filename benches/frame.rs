@@ -0,0 +1,128 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use ggrs::{GGRSRequest, GameStateCell, InputStatus};
+use good_game_rusty_pong::{
+    game_state::GameState, ggrs_config::GGRSConfig, input::Input, DEFAULT_MAX_PREDICTION_WINDOW,
+    INPUT_LEFT_PADDLE_UP, INPUT_RIGHT_PADDLE_DOWN,
+};
+
+fn inputs() -> Vec<(Input, InputStatus)> {
+    vec![
+        (
+            Input {
+                inp: INPUT_LEFT_PADDLE_UP,
+            },
+            InputStatus::Confirmed,
+        ),
+        (
+            Input {
+                inp: INPUT_RIGHT_PADDLE_DOWN,
+            },
+            InputStatus::Predicted,
+        ),
+    ]
+}
+
+/// Return a game state a few frames into the match, and a cell holding it
+fn saved_state() -> (GameState, GameStateCell<GameState>) {
+    let mut state = GameState::new();
+    for _ in 0..10 {
+        state.advance(inputs(), &[]);
+    }
+    let cell = GameStateCell::default();
+    state.handle_requests(
+        vec![GGRSRequest::SaveGameState {
+            cell: cell.clone(),
+            frame: state.frame(),
+        }],
+        &[],
+        None,
+    );
+    (state, cell)
+}
+
+/// Requests GGRS hands over on a rollback of `depth` frames: load the oldest state,
+/// then advance and save every frame again
+fn rollback_requests(
+    cell: &GameStateCell<GameState>,
+    frame: i32,
+    depth: usize,
+) -> Vec<GGRSRequest<GGRSConfig>> {
+    let mut requests = vec![GGRSRequest::LoadGameState {
+        cell: cell.clone(),
+        frame,
+    }];
+    for i in 1..=depth as i32 {
+        requests.push(GGRSRequest::AdvanceFrame { inputs: inputs() });
+        requests.push(GGRSRequest::SaveGameState {
+            cell: GameStateCell::default(),
+            frame: frame + i,
+        });
+    }
+    requests
+}
+
+fn frame(c: &mut Criterion) {
+    let (state, cell) = saved_state();
+    let mut group = c.benchmark_group("frame");
+
+    group.bench_function("save", |b| {
+        b.iter_batched(
+            || {
+                vec![GGRSRequest::SaveGameState {
+                    cell: cell.clone(),
+                    frame: state.frame(),
+                }]
+            },
+            |requests| {
+                let mut state = state;
+                state.handle_requests(requests, &[], None)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("load", |b| {
+        b.iter_batched(
+            || {
+                vec![GGRSRequest::LoadGameState {
+                    cell: cell.clone(),
+                    frame: state.frame(),
+                }]
+            },
+            |requests| {
+                let mut loaded = GameState::new();
+                loaded.handle_requests(requests, &[], None);
+                black_box(loaded)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("advance", |b| {
+        b.iter_batched(
+            inputs,
+            |inputs| {
+                let mut state = state;
+                state.advance(inputs, &[]);
+                black_box(state)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.throughput(Throughput::Elements(DEFAULT_MAX_PREDICTION_WINDOW as u64));
+    group.bench_function("rollback at max prediction window", |b| {
+        b.iter_batched(
+            || rollback_requests(&cell, state.frame(), DEFAULT_MAX_PREDICTION_WINDOW),
+            |requests| {
+                let mut state = state;
+                state.handle_requests(requests, &[], None);
+                black_box(state)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, frame);
+criterion_main!(benches);
@@ -1,13 +1,16 @@
-use macroquad::{prelude::WHITE, shapes::draw_rectangle};
+use std::hash::Hasher;
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    traits::{Drawable, Movable},
+    helpers::StateHasher,
+    render::CommandList,
+    theme::Theme,
+    traits::{Drawable, Movable, StateHash},
     BALL_SIZE, EDGE_BOTTOM, EDGE_LEFT, EDGE_RIGHT, EDGE_TOP, INITIAL_VELOCITY, MIDDLE_POS,
-    PADDLE_HEIGHT,
 };
 
-#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default)]
 pub struct Ball {
     /// Ball's X position
     pub pos_x: i32,
@@ -34,19 +37,23 @@ impl Ball {
     /// assert_eq!(ball.changed_direction, false);
     /// ```
     pub fn new() -> Self {
+        Self::with_speed(INITIAL_VELOCITY)
+    }
+
+    /// Return a new ball at the (0, 0) position, with velocity of `speed` in both x direction and y direction
+    pub fn with_speed(speed: i32) -> Self {
         Ball {
             pos_x: 0,
             pos_y: 0,
-            vel_x: INITIAL_VELOCITY,
-            vel_y: INITIAL_VELOCITY,
+            vel_x: speed,
+            vel_y: speed,
             changed_direction: false,
         }
     }
 
-    /// Return true if the ball's Y position is outside a paddle's Y positions.
-    /// This takes the [`PADDLE_HEIGHT`] into consideration
-    pub fn missed_paddle(&self, paddle_pos: i32) -> bool {
-        self.pos_y < paddle_pos || self.pos_y > paddle_pos + (PADDLE_HEIGHT as i32)
+    /// Return true if the ball's Y position is outside the Y positions of a paddle of `paddle_height`
+    pub fn missed_paddle(&self, paddle_pos: i32, paddle_height: i32) -> bool {
+        self.pos_y < paddle_pos || self.pos_y > paddle_pos + paddle_height
     }
 
     /// Reset the ball's position to the [`MIDDLE_POS`]
@@ -84,16 +91,27 @@ impl Movable for Ball {
     }
 }
 
+impl StateHash for Ball {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_i32(self.pos_x);
+        hasher.write_i32(self.pos_y);
+        hasher.write_i32(self.vel_x);
+        hasher.write_i32(self.vel_y);
+        hasher.write_u8(self.changed_direction as u8);
+    }
+}
+
 impl Drawable for Ball {
-    /// Draw a rectangle using [`draw_rectangle()`] to represent the ball
-    fn draw(&self, x: f32, y: f32) {
-        draw_rectangle(x, y, BALL_SIZE, BALL_SIZE, WHITE);
+    /// Draw a rectangle to represent the ball
+    fn draw(&self, commands: &mut CommandList, theme: &Theme, x: f32, y: f32) {
+        commands.rectangle(x, y, BALL_SIZE, BALL_SIZE, theme.ball);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::PADDLE_HEIGHT;
 
     #[test]
     fn move_ball() {
@@ -176,22 +194,33 @@ mod tests {
         assert_eq!(ball.changed_direction, false);
     }
 
+    #[test]
+    fn taller_paddle_catches_the_ball() {
+        let mut ball = Ball::new();
+        ball.pos_y = PADDLE_HEIGHT as i32 + 5;
+        assert_eq!(ball.missed_paddle(0, PADDLE_HEIGHT as i32), true);
+        assert_eq!(ball.missed_paddle(0, PADDLE_HEIGHT as i32 + 10), false);
+    }
+
     #[test]
     fn above_paddle() {
         let ball = Ball::new();
-        assert_eq!(ball.missed_paddle(ball.pos_y + 1), true);
+        assert_eq!(
+            ball.missed_paddle(ball.pos_y + 1, PADDLE_HEIGHT as i32),
+            true
+        );
     }
 
     #[test]
     fn below_paddle() {
         let mut ball = Ball::new();
         ball.pos_y = PADDLE_HEIGHT as i32;
-        assert_eq!(ball.missed_paddle(-1), true);
+        assert_eq!(ball.missed_paddle(-1, PADDLE_HEIGHT as i32), true);
     }
 
     #[test]
     fn hit_paddle() {
         let ball = Ball::new();
-        assert_eq!(ball.missed_paddle(-1), false);
+        assert_eq!(ball.missed_paddle(-1, PADDLE_HEIGHT as i32), false);
     }
 }
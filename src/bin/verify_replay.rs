@@ -0,0 +1,48 @@
+use std::{env, process::ExitCode};
+
+use good_game_rusty_pong::replay::{Replay, SimulationConstants};
+
+/// Re-run a replay file through the simulation without a window, and compare the result with its stored checksums
+fn main() -> ExitCode {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("Usage: verify_replay <replay file>");
+        return ExitCode::from(2);
+    };
+
+    let replay = match Replay::load(&path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("Could not load replay {path}: {e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    if replay.header.constants != SimulationConstants::current() {
+        eprintln!(
+            "Warning: replay recorded by version {} with different gameplay constants",
+            replay.header.game_version
+        );
+    }
+
+    let run = replay.simulate();
+    println!("rules: {:?}", replay.header.rules);
+    println!("frames: {}", run.state.frame());
+    println!(
+        "score: {} - {}",
+        run.state.left_paddle.score, run.state.right_paddle.score
+    );
+    for (frame, checksum) in &run.checksums {
+        println!("checksum at frame {frame}: {checksum:#018x}");
+    }
+
+    match replay.verify(&run) {
+        Ok(matched) => {
+            println!("OK: all {matched} checksums match");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use macroquad::prelude::*;
+
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// Return the part of a `window_width` by `window_height` window the playfield is drawn to:
+/// the largest centered rectangle with the aspect ratio of the playfield, leaving black bars on the sides
+pub fn letterbox(window_width: f32, window_height: f32) -> Rect {
+    let scale = (window_width / SCREEN_WIDTH).min(window_height / SCREEN_HEIGHT);
+    let width = SCREEN_WIDTH * scale;
+    let height = SCREEN_HEIGHT * scale;
+    Rect::new(
+        (window_width - width) / 2.0,
+        (window_height - height) / 2.0,
+        width,
+        height,
+    )
+}
+
+/// Draw everything that follows in playfield coordinates, from (0, 0) to ([`SCREEN_WIDTH`], [`SCREEN_HEIGHT`]),
+/// scaled to fit the window. [`set_default_camera`] goes back to window coordinates.
+pub fn set_playfield_camera() {
+    let viewport = letterbox(screen_width(), screen_height());
+    set_camera(&Camera2D {
+        viewport: Some((
+            viewport.x as i32,
+            viewport.y as i32,
+            viewport.w as i32,
+            viewport.h as i32,
+        )),
+        ..Camera2D::from_display_rect(Rect::new(0.0, 0.0, SCREEN_WIDTH, SCREEN_HEIGHT))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_multiple_fills_the_window() {
+        let viewport = letterbox(SCREEN_WIDTH * 2.0, SCREEN_HEIGHT * 2.0);
+        assert_eq!(
+            viewport,
+            Rect::new(0.0, 0.0, SCREEN_WIDTH * 2.0, SCREEN_HEIGHT * 2.0)
+        );
+    }
+
+    #[test]
+    fn wide_window_has_bars_on_the_left_and_right() {
+        let viewport = letterbox(1920.0, SCREEN_HEIGHT * 3.0);
+        assert_eq!(viewport.y, 0.0);
+        assert_eq!(viewport.h, SCREEN_HEIGHT * 3.0);
+        assert_eq!(viewport.w, SCREEN_WIDTH * 3.0);
+        assert_eq!(viewport.x, (1920.0 - SCREEN_WIDTH * 3.0) / 2.0);
+    }
+
+    #[test]
+    fn tall_window_has_bars_on_the_top_and_bottom() {
+        let viewport = letterbox(SCREEN_WIDTH, 1000.0);
+        assert_eq!(viewport.x, 0.0);
+        assert_eq!(viewport.w, SCREEN_WIDTH);
+        assert_eq!(viewport.y, (1000.0 - SCREEN_HEIGHT) / 2.0);
+    }
+}
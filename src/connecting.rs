@@ -0,0 +1,136 @@
+use std::mem;
+
+use async_executor::LocalExecutor;
+use macroquad::prelude::*;
+use matchbox_socket::WebRtcSocket;
+
+use crate::{
+    handshake::{Handshake, PeerRole},
+    match_screen::MatchScreen,
+    online_match::OnlineDriver,
+    room::Room,
+    screen::{Context, Screen, Transition},
+    session_settings::InputDelay,
+    spectating::SpectatorDriver,
+    DEFAULT_INPUT_DELAY, NUM_PLAYERS,
+};
+
+/// Loading screen while the connection to the other peers is being established and the match agreed on
+pub struct Connecting {
+    room: Room,
+    /// The socket, only taken when the match starts
+    socket: Option<WebRtcSocket>,
+    handshake: Handshake,
+    /// Runs the message loop of the socket, handed over to the match once it starts
+    executor: LocalExecutor<'static>,
+}
+
+impl Connecting {
+    /// Join the `room` of the matchbox `server`, and go through the `handshake` with the peers found there
+    pub fn join(room: &Room, server: &str, handshake: Handshake) -> Self {
+        info!("Constructing socket for {room}...");
+        let room_url = room.url(server);
+        let (socket, message_loop) = WebRtcSocket::new(room_url);
+        let executor = LocalExecutor::new();
+        executor.spawn(message_loop).detach();
+        Self {
+            room: room.clone(),
+            socket: Some(socket),
+            handshake,
+            executor,
+        }
+    }
+
+    /// Return the input delay of the match, or none while the ping to the other player is still being measured
+    fn input_delay(&mut self, ctx: &Context) -> Option<usize> {
+        match ctx.settings.input_delay {
+            InputDelay::Fixed(frames) => Some(frames),
+            InputDelay::Auto => {
+                let probe = self.handshake.probe();
+                if !probe.is_done() {
+                    return None;
+                }
+                let ping = probe.median_ping_ms();
+                let frames = ping.map_or(DEFAULT_INPUT_DELAY, |ping| {
+                    InputDelay::from_ping(ping, ctx.settings.fps)
+                });
+                info!("Measured ping {ping:?} ms, using input delay of {frames} frames");
+                Some(frames)
+            }
+        }
+    }
+}
+
+impl Screen for Connecting {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        // user can abort
+        if is_key_pressed(KeyCode::Escape) {
+            return Transition::Pop;
+        }
+
+        let socket = self
+            .socket
+            .as_mut()
+            .expect("The socket is only taken when leaving the screen.");
+        self.executor.try_tick();
+        socket.accept_new_connections();
+        self.handshake.update(socket);
+        if self.handshake.is_refused() {
+            return Transition::None;
+        }
+
+        let remote_players = self.handshake.remote_ids(PeerRole::Player).len();
+        let has_rules = self.handshake.match_rules(socket.id()).is_some();
+        let game = match self.handshake.role() {
+            // if there is a connected player, and the host announced the rules
+            PeerRole::Player if remote_players > 0 && has_rules => {
+                let Some(input_delay) = self.input_delay(ctx) else {
+                    return Transition::None;
+                };
+                let socket = self.socket.take().expect("The socket was just polled.");
+                let executor = mem::replace(&mut self.executor, LocalExecutor::new());
+                OnlineDriver::start(
+                    socket,
+                    &self.handshake,
+                    executor,
+                    &ctx.settings,
+                    input_delay,
+                )
+            }
+            // if both players are connected, and the host announced the rules
+            PeerRole::Spectator if remote_players == NUM_PLAYERS && has_rules => {
+                let socket = self.socket.take().expect("The socket was just polled.");
+                let executor = mem::replace(&mut self.executor, LocalExecutor::new());
+                SpectatorDriver::start(socket, &self.handshake, executor, &ctx.settings)
+            }
+            _ => return Transition::None,
+        };
+        Transition::Replace(Box::new(MatchScreen::new(game)))
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        let status = if self.handshake.is_refused() {
+            "The match already started"
+        } else if self.handshake.is_probing() {
+            "Measuring ping..."
+        } else if self.handshake.role() == PeerRole::Spectator {
+            "Waiting for the players..."
+        } else {
+            "Waiting for the other player..."
+        };
+        let params = TextParams {
+            font_size: 30,
+            font: ctx.backend.font(),
+            ..Default::default()
+        };
+        draw_text_ex(status, 15.0, 35.0, params);
+        // the code of a private room is shown until the friend it was sent to joins
+        if let Room::Named(name) = &self.room {
+            draw_text_ex(&format!("Room: {name}"), 15.0, 75.0, params);
+        }
+        if self.handshake.is_refused() {
+            draw_text_ex("Spectators join before the players,", 15.0, 115.0, params);
+            draw_text_ex("press ESC to go back", 15.0, 155.0, params);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{
+    game_state::{GameState, Side},
+    MIDDLE_POS,
+};
+
+/// Strength of a computer-controlled player.
+/// Its moves only depend on the game state, so they are the same on every peer and when replaying or rolling back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CpuLevel {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl CpuLevel {
+    pub const ALL: [CpuLevel; 3] = [CpuLevel::Easy, CpuLevel::Medium, CpuLevel::Hard];
+
+    /// Parse a level from a command-line value, either its name or its number from 1 to 3
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::cpu::CpuLevel;
+    /// assert_eq!(CpuLevel::parse("hard"), Some(CpuLevel::Hard));
+    /// assert_eq!(CpuLevel::parse("1"), Some(CpuLevel::Easy));
+    /// assert_eq!(CpuLevel::parse("impossible"), None);
+    /// ```
+    pub fn parse(value: &str) -> Option<Self> {
+        CpuLevel::ALL
+            .into_iter()
+            .enumerate()
+            .find_map(|(i, level)| {
+                let matches =
+                    value.eq_ignore_ascii_case(level.name()) || value == (i + 1).to_string();
+                matches.then_some(level)
+            })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CpuLevel::Easy => "easy",
+            CpuLevel::Medium => "medium",
+            CpuLevel::Hard => "hard",
+        }
+    }
+
+    /// Frames between two decisions, the paddle keeps moving the same way in between
+    fn reaction_frames(self) -> i32 {
+        match self {
+            CpuLevel::Easy => 12,
+            CpuLevel::Medium => 6,
+            CpuLevel::Hard => 1,
+        }
+    }
+
+    /// Distance in pixels between the center of the paddle and its target under which the paddle stops
+    fn tolerance(self) -> i32 {
+        match self {
+            CpuLevel::Easy => 24,
+            CpuLevel::Medium => 12,
+            CpuLevel::Hard => 4,
+        }
+    }
+
+    /// Return the paddle input bits of a computer-controlled player on `side` of `state`.
+    /// It follows the ball coming its way, and, unless easy, goes back to the middle once the ball left.
+    pub fn input(self, state: &GameState, side: Side) -> u8 {
+        let (paddle, ball_coming) = match side {
+            Side::Left => (&state.left_paddle, state.ball.vel_x < 0),
+            Side::Right => (&state.right_paddle, state.ball.vel_x > 0),
+        };
+        let (up, down) = side.paddle_inputs();
+
+        if state.frame() % self.reaction_frames() != 0 {
+            return match paddle.vel.signum() {
+                -1 => up,
+                1 => down,
+                _ => 0,
+            };
+        }
+
+        let target = if ball_coming {
+            state.ball.pos_y
+        } else if self == CpuLevel::Easy {
+            return 0;
+        } else {
+            MIDDLE_POS[1]
+        };
+        let center = paddle.pos + paddle.height / 2;
+        if target < center - self.tolerance() {
+            up
+        } else if target > center + self.tolerance() {
+            down
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{INPUT_RIGHT_PADDLE_DOWN, INPUT_RIGHT_PADDLE_UP};
+
+    #[test]
+    fn follows_the_ball_coming_its_way() {
+        let mut state = GameState::new();
+        state.ball.vel_x = 3;
+        state.right_paddle.pos = 100;
+        state.ball.pos_y = 10;
+        assert_eq!(
+            CpuLevel::Hard.input(&state, Side::Right),
+            INPUT_RIGHT_PADDLE_UP
+        );
+        state.ball.pos_y = 300;
+        assert_eq!(
+            CpuLevel::Hard.input(&state, Side::Right),
+            INPUT_RIGHT_PADDLE_DOWN
+        );
+        state.ball.pos_y = 125;
+        assert_eq!(CpuLevel::Hard.input(&state, Side::Right), 0);
+    }
+
+    #[test]
+    fn easy_waits_for_the_ball() {
+        let mut state = GameState::new();
+        state.ball.vel_x = -3;
+        state.right_paddle.pos = 1;
+        assert_eq!(CpuLevel::Easy.input(&state, Side::Right), 0);
+        assert_eq!(
+            CpuLevel::Medium.input(&state, Side::Right),
+            INPUT_RIGHT_PADDLE_DOWN
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use ggrs::{NetworkStats, P2PSession};
+use instant::{Duration, Instant};
+use macroquad::prelude::*;
+
+use crate::ggrs_config::GGRSConfig;
+
+/// Counts events in consecutive one second windows
+#[derive(Debug)]
+pub struct PerSecondCounter {
+    /// Events counted in the current window
+    current: u32,
+    /// Events counted in the last completed window
+    last: u32,
+    /// When the current window started
+    window_start: Instant,
+}
+
+impl PerSecondCounter {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            current: 0,
+            last: 0,
+            window_start: now,
+        }
+    }
+
+    /// Count `amount` events in the current window
+    pub fn add(&mut self, amount: u32) {
+        self.current += amount;
+    }
+
+    /// Close the current window if a second has passed since it started
+    /// # Examples
+    /// ```
+    /// # use instant::{Duration, Instant};
+    /// # use good_game_rusty_pong::debug_overlay::PerSecondCounter;
+    /// let start = Instant::now();
+    /// let mut counter = PerSecondCounter::new(start);
+    /// counter.add(3);
+    /// counter.update(start + Duration::from_millis(500));
+    /// assert_eq!(counter.per_second(), 0);
+    /// counter.update(start + Duration::from_secs(1));
+    /// assert_eq!(counter.per_second(), 3);
+    /// ```
+    pub fn update(&mut self, now: Instant) {
+        if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+            self.last = self.current;
+            self.current = 0;
+            self.window_start = now;
+        }
+    }
+
+    /// Return the amount of events counted in the last completed window
+    pub fn per_second(&self) -> u32 {
+        self.last
+    }
+}
+
+/// Toggleable overlay displaying the GGRS network statistics of the current match
+pub struct DebugOverlay {
+    /// True if the overlay is drawn
+    visible: bool,
+    /// Amount of rollbacks (state loads) requested by GGRS
+    rollbacks: PerSecondCounter,
+    /// Amount of frames skipped due to [`ggrs::GGRSError::PredictionThreshold`]
+    stalls: PerSecondCounter,
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            visible: false,
+            rollbacks: PerSecondCounter::new(now),
+            stalls: PerSecondCounter::new(now),
+        }
+    }
+
+    /// Show the overlay if hidden, hide it otherwise
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Count rollbacks performed in the current frame
+    pub fn record_rollbacks(&mut self, amount: u32) {
+        self.rollbacks.add(amount);
+    }
+
+    /// Count a frame that could not be advanced because of the prediction threshold
+    pub fn record_stall(&mut self) {
+        self.stalls.add(1);
+    }
+
+    /// Draw the statistics of every remote player in the top left corner of the screen
+    pub fn draw(&mut self, session: &P2PSession<GGRSConfig>, font: Font) {
+        let now = Instant::now();
+        self.rollbacks.update(now);
+        self.stalls.update(now);
+
+        if !self.visible {
+            return;
+        }
+
+        let mut lines = Vec::new();
+        for handle in session.remote_player_handles() {
+            match session.network_stats(handle) {
+                Ok(stats) => lines.extend(Self::stats_lines(handle, &stats)),
+                Err(_) => lines.push(format!("P{handle}: no stats yet")),
+            }
+        }
+        lines.push(format!("frames ahead: {}", session.frames_ahead()));
+        lines.push(format!("rollbacks/s: {}", self.rollbacks.per_second()));
+        lines.push(format!("stalls/s: {}", self.stalls.per_second()));
+
+        let line_height = 14.0;
+        draw_rectangle(
+            0.0,
+            0.0,
+            160.0,
+            line_height * lines.len() as f32 + 6.0,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+        for (i, line) in lines.iter().enumerate() {
+            draw_text_ex(
+                line,
+                4.0,
+                line_height * (i + 1) as f32,
+                TextParams {
+                    font_size: 12,
+                    font,
+                    color: YELLOW,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    fn stats_lines(handle: usize, stats: &NetworkStats) -> Vec<String> {
+        vec![
+            format!("P{handle} ping: {} ms", stats.ping),
+            format!("P{handle} send queue: {}", stats.send_queue_len),
+            format!("P{handle} kbps: {}", stats.kbps_sent),
+            format!(
+                "P{handle} behind: {} / {}",
+                stats.local_frames_behind, stats.remote_frames_behind
+            ),
+        ]
+    }
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::{
+    game_state::{GameState, Side},
+    interpolation::RenderPositions,
+    render::CommandList,
+    rng::Rng,
+    theme::Theme,
+    BALL_SIZE, EFFECTS_FLASH_SECONDS, EFFECTS_PARTICLES, EFFECTS_PARTICLE_SECONDS,
+    EFFECTS_SHAKE_SECONDS, EFFECTS_SHAKE_STRENGTH, EFFECTS_TRAIL_LENGTH, PADDLE_WIDTH,
+    SCREEN_WIDTH,
+};
+
+/// Gameplay event that happened between two consecutive displayed frames
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameEvent {
+    /// The ball bounced off the paddle on that side
+    PaddleHit(Side),
+    /// The paddle on that side scored a point
+    Goal(Side),
+}
+
+impl GameEvent {
+    /// Return the events that happened going from `previous` to `current`,
+    /// or none if `current` is not the frame right after `previous`
+    pub fn between(previous: &GameState, current: &GameState) -> Vec<GameEvent> {
+        if current.frame() != previous.frame() + 1 || previous.winner.is_some() {
+            return Vec::new();
+        }
+
+        let scored = current.left_paddle.score != previous.left_paddle.score
+            || current.right_paddle.score != previous.right_paddle.score
+            || current.sets != previous.sets
+            || current.winner.is_some();
+        if scored {
+            // the ball left the playfield on the side it was moving to
+            let scorer = if previous.ball.vel_x > 0 {
+                Side::Left
+            } else {
+                Side::Right
+            };
+            vec![GameEvent::Goal(scorer)]
+        } else if current.ball.changed_direction {
+            let side = if current.ball.vel_x < 0 {
+                Side::Right
+            } else {
+                Side::Left
+            };
+            vec![GameEvent::PaddleHit(side)]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+struct Particle {
+    pos: Vec2,
+    vel: Vec2,
+    /// Seconds left before the particle disappears
+    life: f32,
+}
+
+/// Cosmetic effects drawn over the playfield: ball trail, paddle flash, screen shake and particles.
+/// They are driven by the displayed frames and live outside of the game state, so rollbacks never touch them.
+pub struct Effects {
+    enabled: bool,
+    /// Latest positions of the ball, oldest first
+    trail: VecDeque<Vec2>,
+    /// Seconds left of the flash of each paddle, indexed by [`Side::index`]
+    flashes: [f32; 2],
+    /// Seconds left of the screen shake
+    shake: f32,
+    shake_offset: Vec2,
+    particles: Vec<Particle>,
+    rng: Rng,
+}
+
+impl Effects {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            trail: VecDeque::with_capacity(EFFECTS_TRAIL_LENGTH),
+            flashes: [0.0; 2],
+            shake: 0.0,
+            shake_offset: Vec2::ZERO,
+            particles: Vec::new(),
+            rng: Rng::new(0),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Turn the effects on or off, dropping the running ones
+    pub fn set_enabled(&mut self, enabled: bool) {
+        *self = Self::new(enabled);
+    }
+
+    /// Follow the game from `previous` to `current`, the frame just simulated
+    pub fn observe(&mut self, previous: &GameState, current: &GameState) {
+        if !self.enabled {
+            return;
+        }
+
+        let ball = vec2(current.ball.pos_x as f32, current.ball.pos_y as f32);
+        for event in GameEvent::between(previous, current) {
+            match event {
+                GameEvent::PaddleHit(side) => {
+                    self.flashes[side.index()] = EFFECTS_FLASH_SECONDS;
+                    self.burst(ball);
+                }
+                GameEvent::Goal(_) => {
+                    self.shake = EFFECTS_SHAKE_SECONDS;
+                    self.trail.clear();
+                }
+            }
+        }
+
+        if self.trail.len() == EFFECTS_TRAIL_LENGTH {
+            self.trail.pop_front();
+        }
+        self.trail.push_back(ball);
+    }
+
+    /// Age the effects by `delta` seconds
+    pub fn update(&mut self, delta: f32) {
+        for flash in &mut self.flashes {
+            *flash = (*flash - delta).max(0.0);
+        }
+
+        self.shake = (self.shake - delta).max(0.0);
+        self.shake_offset = if self.shake > 0.0 {
+            // the shake calms down as it ends
+            let strength = EFFECTS_SHAKE_STRENGTH * self.shake / EFFECTS_SHAKE_SECONDS;
+            vec2(
+                self.rng.range(-100, 101) as f32,
+                self.rng.range(-100, 101) as f32,
+            ) * strength
+                / 100.0
+        } else {
+            Vec2::ZERO
+        };
+
+        for particle in &mut self.particles {
+            particle.pos += particle.vel * delta;
+            particle.life -= delta;
+        }
+        self.particles.retain(|particle| particle.life > 0.0);
+    }
+
+    /// Offset the whole playfield is drawn at while the screen shakes
+    pub fn shake_offset(&self) -> Vec2 {
+        self.shake_offset
+    }
+
+    /// Record the effects drawn behind the playfield objects
+    pub fn draw_under(&self, commands: &mut CommandList, theme: &Theme) {
+        if !self.enabled {
+            return;
+        }
+
+        let len = self.trail.len() as f32;
+        for (i, pos) in self.trail.iter().enumerate() {
+            let alpha = 0.4 * (i + 1) as f32 / (len + 1.0);
+            commands.rectangle(
+                pos.x,
+                pos.y,
+                BALL_SIZE,
+                BALL_SIZE,
+                Color {
+                    a: alpha,
+                    ..theme.ball
+                },
+            );
+        }
+    }
+
+    /// Record the effects drawn over the playfield objects, the paddles being drawn at `positions`
+    pub fn draw_over(
+        &self,
+        commands: &mut CommandList,
+        theme: &Theme,
+        state: &GameState,
+        positions: RenderPositions,
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        let paddles = [
+            (0.0, positions.left_paddle, state.left_paddle.height),
+            (
+                SCREEN_WIDTH - PADDLE_WIDTH,
+                positions.right_paddle,
+                state.right_paddle.height,
+            ),
+        ];
+        for ((x, y, height), flash) in paddles.into_iter().zip(self.flashes) {
+            if flash > 0.0 {
+                let alpha = flash / EFFECTS_FLASH_SECONDS;
+                commands.rectangle(
+                    x,
+                    y,
+                    PADDLE_WIDTH,
+                    height as f32,
+                    Color {
+                        a: theme.paddle_flash.a * alpha,
+                        ..theme.paddle_flash
+                    },
+                );
+            }
+        }
+
+        for particle in &self.particles {
+            let alpha = particle.life / EFFECTS_PARTICLE_SECONDS;
+            commands.rectangle(
+                particle.pos.x,
+                particle.pos.y,
+                2.0,
+                2.0,
+                Color {
+                    a: alpha,
+                    ..theme.ball
+                },
+            );
+        }
+    }
+
+    /// Throw particles in every direction from the center of the ball at `pos`
+    fn burst(&mut self, pos: Vec2) {
+        let center = pos + Vec2::splat(BALL_SIZE / 2.0);
+        for _ in 0..EFFECTS_PARTICLES {
+            let vel = vec2(
+                self.rng.range(-120, 121) as f32,
+                self.rng.range(-120, 121) as f32,
+            );
+            self.particles.push(Particle {
+                pos: center,
+                vel,
+                life: EFFECTS_PARTICLE_SECONDS,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EDGE_LEFT, EDGE_RIGHT};
+
+    fn next_frame(state: &GameState) -> GameState {
+        let mut next = *state;
+        next.advance(Vec::new(), &[]);
+        next
+    }
+
+    #[test]
+    fn paddle_hit_event() {
+        let mut previous = GameState::new();
+        previous.ball.pos_x = EDGE_RIGHT - 1;
+        previous.ball.pos_y = previous.right_paddle.pos + 10;
+        let current = next_frame(&previous);
+        assert_eq!(
+            GameEvent::between(&previous, &current),
+            vec![GameEvent::PaddleHit(Side::Right)]
+        );
+    }
+
+    #[test]
+    fn goal_event() {
+        let mut previous = GameState::new();
+        previous.ball.pos_x = EDGE_LEFT + 1;
+        previous.ball.vel_x = -3;
+        previous.ball.pos_y = 200;
+        let current = next_frame(&previous);
+        assert_eq!(
+            GameEvent::between(&previous, &current),
+            vec![GameEvent::Goal(Side::Right)]
+        );
+    }
+
+    #[test]
+    fn no_events_across_skipped_frames() {
+        let mut previous = GameState::new();
+        previous.ball.pos_x = EDGE_RIGHT - 1;
+        previous.ball.pos_y = previous.right_paddle.pos + 10;
+        let current = next_frame(&next_frame(&previous));
+        assert!(GameEvent::between(&previous, &current).is_empty());
+    }
+
+    #[test]
+    fn goal_shakes_the_screen_until_it_fades() {
+        let mut previous = GameState::new();
+        previous.ball.pos_x = EDGE_LEFT + 1;
+        previous.ball.vel_x = -3;
+        previous.ball.pos_y = 200;
+        let current = next_frame(&previous);
+
+        let mut effects = Effects::new(true);
+        effects.observe(&previous, &current);
+        assert_eq!(effects.shake, EFFECTS_SHAKE_SECONDS);
+        effects.update(EFFECTS_SHAKE_SECONDS);
+        assert_eq!(effects.shake_offset(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn disabled_effects_draw_nothing() {
+        let mut previous = GameState::new();
+        previous.ball.pos_x = EDGE_RIGHT - 1;
+        previous.ball.pos_y = previous.right_paddle.pos + 10;
+        let current = next_frame(&previous);
+
+        let mut effects = Effects::new(false);
+        effects.observe(&previous, &current);
+        effects.update(0.01);
+        let mut commands = CommandList::new();
+        let theme = Theme::classic();
+        effects.draw_under(&mut commands, &theme);
+        effects.draw_over(
+            &mut commands,
+            &theme,
+            &current,
+            RenderPositions::of(&current),
+        );
+        assert!(commands.commands().is_empty());
+        assert_eq!(effects.shake_offset(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn particles_fade_out() {
+        let mut effects = Effects::new(true);
+        effects.burst(Vec2::ZERO);
+        assert_eq!(effects.particles.len(), EFFECTS_PARTICLES);
+        effects.update(EFFECTS_PARTICLE_SECONDS);
+        assert!(effects.particles.is_empty());
+    }
+}
@@ -1,17 +1,61 @@
+use std::hash::Hasher;
+
 use ggrs::{Frame, GGRSRequest, GameStateCell, InputStatus, NULL_FRAME};
-use macroquad::{
-    audio::{play_sound_once, Sound},
-    prelude::*,
-};
+use macroquad::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    ball::Ball, ggrs_config::GGRSConfig, helpers::fletcher16, input::Input, paddle::Paddle,
-    traits::Movable, INPUT_LEFT_PADDLE_DOWN, INPUT_LEFT_PADDLE_UP, INPUT_RIGHT_PADDLE_DOWN,
-    INPUT_RIGHT_PADDLE_UP,
+    ball::Ball,
+    ggrs_config::GGRSConfig,
+    helpers::StateHasher,
+    input::Input,
+    key_bindings::{KeyAction, KeyBindings},
+    match_rules::MatchRules,
+    paddle::Paddle,
+    replay::ReplayRecorder,
+    rng::Rng,
+    sound::GameSound,
+    traits::{Movable, StateHash},
+    EDGE_LEFT, EDGE_RIGHT, INPUT_FORFEIT, INPUT_LEFT_PADDLE_DOWN, INPUT_LEFT_PADDLE_UP,
+    INPUT_PAUSE, INPUT_REMATCH, INPUT_RESUME, INPUT_RIGHT_PADDLE_DOWN, INPUT_RIGHT_PADDLE_UP,
+    MAX_PAUSES_PER_PLAYER, NUM_PLAYERS,
 };
 
-#[derive(Clone, Default, Serialize, Deserialize)]
+/// Side of the playfield
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    /// Index of the side in the arrays holding a value for both sides, like [`GameState::sets`]
+    pub fn index(self) -> usize {
+        match self {
+            Side::Left => 0,
+            Side::Right => 1,
+        }
+    }
+
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    /// Input bits moving the paddle of the side up and down
+    pub fn paddle_inputs(self) -> (u8, u8) {
+        match self {
+            Side::Left => (INPUT_LEFT_PADDLE_UP, INPUT_LEFT_PADDLE_DOWN),
+            Side::Right => (INPUT_RIGHT_PADDLE_UP, INPUT_RIGHT_PADDLE_DOWN),
+        }
+    }
+}
+
+/// State of the simulation, saved and loaded by GGRS on rollbacks.
+/// Its fields are part of the [`crate::snapshot`] format, which must be versioned when they change.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
 pub struct GameState {
     frame: i32,
     last_checksum: (Frame, u64),
@@ -20,78 +64,179 @@ pub struct GameState {
     pub left_paddle: Paddle,
     pub right_paddle: Paddle,
     pub ball: Ball,
+    /// Side of the paddle that won the match, if it is over
+    pub winner: Option<Side>,
+    /// True for every player handle that asked for a rematch since the match is over
+    pub rematch_votes: [bool; NUM_PLAYERS],
+    /// Source of all the randomness of the simulation, seeded identically on every peer
+    pub rng: Rng,
+    /// Rules of the match, identical on every peer
+    pub rules: MatchRules,
+    /// Sets won by the paddle on the left side and by the paddle on the right side
+    pub sets: [i32; 2],
+    /// True if the players switched sides an odd number of times
+    pub sides_switched: bool,
+    /// Handle of the player who paused the match, if it is paused
+    pub paused_by: Option<usize>,
+    /// Pauses every player handle can still ask for during the match
+    pub pauses_left: [u8; NUM_PLAYERS],
+    /// True for every player handle that agreed to resume the paused match
+    pub resume_votes: [bool; NUM_PLAYERS],
 }
 
 impl GameState {
-    /// Return a new game state with an initial [`Paddle`] for both left and right paddles,
+    /// Return a new game state with the classic [`MatchRules`], an initial [`Paddle`] for both left and right paddles,
     /// an initial [`Ball`] for the ball, and frame 0
     pub fn new() -> Self {
-        let left_paddle = Paddle::new();
-        let right_paddle = Paddle::new();
-        let ball = Ball::new();
+        Self::for_match(MatchRules::classic(), 0)
+    }
 
+    /// Return a new game state at frame 0 playing by `rules`, whose random source is seeded with `seed`
+    pub fn for_match(rules: MatchRules, seed: u64) -> Self {
         Self {
             frame: 0,
             last_checksum: (NULL_FRAME, 0),
             periodic_checksum: (NULL_FRAME, 0),
             sound_played: 0,
-            left_paddle,
-            right_paddle,
-            ball,
+            left_paddle: Paddle::with_height(rules.paddle_height),
+            right_paddle: Paddle::with_height(rules.paddle_height),
+            ball: Ball::with_speed(rules.ball_speed),
+            winner: None,
+            rematch_votes: [false; NUM_PLAYERS],
+            rng: Rng::new(seed),
+            rules,
+            sets: [0; 2],
+            sides_switched: false,
+            paused_by: None,
+            pauses_left: [MAX_PAUSES_PER_PLAYER; NUM_PLAYERS],
+            resume_votes: [false; NUM_PLAYERS],
         }
     }
 
+    /// Return the amount of frames simulated since the session started
+    pub fn frame(&self) -> i32 {
+        self.frame
+    }
+
     /// Handle GGRS requests and update the game state accordingly.
-    pub fn handle_requests(&mut self, requests: Vec<GGRSRequest<GGRSConfig>>, sounds: &[Sound]) {
+    /// If a [`ReplayRecorder`] is given, it follows the loaded, saved and advanced frames.
+    pub fn handle_requests(
+        &mut self,
+        requests: Vec<GGRSRequest<GGRSConfig>>,
+        sounds: &[GameSound],
+        mut recorder: Option<&mut ReplayRecorder>,
+    ) {
         for request in requests {
             match request {
-                GGRSRequest::LoadGameState { cell, .. } => self.load_game_state(cell),
-                GGRSRequest::SaveGameState { cell, frame } => self.save_game_state(cell, frame),
-                GGRSRequest::AdvanceFrame { inputs } => self.advance(inputs, sounds.to_vec()),
+                GGRSRequest::LoadGameState { cell, frame } => {
+                    self.load_game_state(cell);
+                    if let Some(recorder) = recorder.as_deref_mut() {
+                        recorder.rollback(frame);
+                    }
+                }
+                GGRSRequest::SaveGameState { cell, frame } => {
+                    let checksum = self.save_game_state(cell, frame);
+                    if let Some(recorder) = recorder.as_deref_mut() {
+                        recorder.save_checksum(frame, checksum);
+                    }
+                }
+                GGRSRequest::AdvanceFrame { inputs } => {
+                    let was_over = self.winner.is_some();
+                    if let Some(recorder) = recorder.as_deref_mut() {
+                        recorder.record(self.frame, &inputs);
+                    }
+                    self.advance(inputs, sounds);
+                    if let Some(recorder) = recorder.as_deref_mut() {
+                        if !was_over && self.winner.is_some() {
+                            recorder.match_ended(self.frame);
+                        }
+                    }
+                }
             }
         }
     }
 
-    /// Save the current game state and create a checksum
-    fn save_game_state(&mut self, cell: GameStateCell<GameState>, frame: Frame) {
+    /// Save a copy of the current game state and its checksum, without allocating, and return the checksum
+    fn save_game_state(&mut self, cell: GameStateCell<GameState>, frame: Frame) -> u64 {
         assert_eq!(self.frame, frame);
-        let buffer = bincode::serialize(&self).unwrap();
-        let checksum = fletcher16(&buffer) as u128;
-        cell.save(frame, Some(self.clone()), Some(checksum));
+        let checksum = self.checksum();
+        cell.save(frame, Some(*self), Some(checksum as u128));
+        checksum
+    }
+
+    /// Return the checksum of the whole game state, hashing its fields in place
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        self.hash_state(&mut hasher);
+        hasher.finish()
     }
 
     /// Load gamestate and overwrite self
     fn load_game_state(&mut self, cell: GameStateCell<GameState>) {
-        let loaded_game_state = cell.load().expect("No data found.");
-        self.clone_from(&loaded_game_state);
+        *self = cell.load().expect("No data found.");
+    }
+
+    /// Return the side played by the player with `handle`, whose paddle its inputs move: the first player starts
+    /// on the left and the second on the right, and they trade sides when sets switch sides
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::game_state::{GameState, Side};
+    /// let mut state = GameState::new();
+    /// assert_eq!(state.side_of(0), Side::Left);
+    /// state.sides_switched = true;
+    /// assert_eq!(state.side_of(0), Side::Right);
+    /// assert_eq!(state.side_of(1), Side::Left);
+    /// ```
+    pub fn side_of(&self, handle: usize) -> Side {
+        let side = if handle == 0 { Side::Left } else { Side::Right };
+        if self.sides_switched {
+            side.opposite()
+        } else {
+            side
+        }
     }
 
-    /// Check for local inputs from the player and return an [`Input`] object
-    pub fn local_input(&self) -> Input {
+    /// Check for local inputs from the player, with the keys of `bindings`, and return an [`Input`] object
+    pub fn local_input(&self, bindings: &KeyBindings) -> Input {
         let mut inp: u8 = 0;
 
-        if is_key_down(KeyCode::Up) {
-            inp |= INPUT_RIGHT_PADDLE_UP;
-        }
-        if is_key_down(KeyCode::Down) {
-            inp |= INPUT_RIGHT_PADDLE_DOWN;
-        }
-        if is_key_down(KeyCode::W) {
-            inp |= INPUT_LEFT_PADDLE_UP;
-        }
-        if is_key_down(KeyCode::S) {
-            inp |= INPUT_LEFT_PADDLE_DOWN;
+        let actions = [
+            (KeyAction::RightPaddleUp, INPUT_RIGHT_PADDLE_UP),
+            (KeyAction::RightPaddleDown, INPUT_RIGHT_PADDLE_DOWN),
+            (KeyAction::LeftPaddleUp, INPUT_LEFT_PADDLE_UP),
+            (KeyAction::LeftPaddleDown, INPUT_LEFT_PADDLE_DOWN),
+            (KeyAction::Rematch, INPUT_REMATCH),
+        ];
+        for (action, bit) in actions {
+            if is_key_down(bindings.key(action)) {
+                inp |= bit;
+            }
         }
 
         Input { inp }
     }
 
     /// Advance the game state's by a single frame and handle the moveable objects according to the received [`Input`]  
-    /// In case the inputs contain an [`InputStatus::Disconnected`] status, all inputs will be ignored
-    pub fn advance(&mut self, inputs_vector: Vec<(Input, InputStatus)>, sounds: Vec<Sound>) {
+    /// Every handle moves the paddle of the side it plays, with the up and down bits of either side  
+    /// In case the inputs contain an [`InputStatus::Disconnected`] status, all inputs will be ignored  
+    /// Once the match is over, only rematch votes are handled, and the match restarts when all players voted  
+    /// While the match is paused, nothing moves until all players voted to resume it or one of them forfeits
+    pub fn advance(&mut self, inputs_vector: Vec<(Input, InputStatus)>, sounds: &[GameSound]) {
         self.frame += 1;
 
-        let movables: Vec<&mut dyn Movable> = vec![
+        if self.winner.is_some() {
+            self.handle_rematch_votes(&inputs_vector);
+            return;
+        }
+        if self.paused_by.is_some() {
+            self.handle_pause_votes(&inputs_vector);
+            return;
+        }
+        if self.handle_pause_requests(&inputs_vector) {
+            return;
+        }
+
+        let movables: [&mut dyn Movable; 3] = [
             &mut self.left_paddle,
             &mut self.right_paddle,
             &mut self.ball,
@@ -101,44 +246,492 @@ impl GameState {
         }
 
         if self.ball.changed_direction {
-            play_sound_once(sounds[self.sound_played]);
+            // no sounds are given when frames are simulated silently
+            if let Some(sound) = sounds.get(self.sound_played) {
+                sound.play();
+            }
             self.sound_played = if self.sound_played == 0 { 1 } else { 0 };
         }
 
-        let mut detected_left = false;
-        let mut detected_right = false;
-
-        for inputs in inputs_vector {
-            let input = match inputs.1 {
-                InputStatus::Confirmed => inputs.0.inp,
-                InputStatus::Predicted => inputs.0.inp,
+        self.left_paddle.vel = 0;
+        self.right_paddle.vel = 0;
+        for (handle, (input, status)) in inputs_vector.iter().enumerate().take(NUM_PLAYERS) {
+            let input = match status {
+                InputStatus::Confirmed | InputStatus::Predicted => input.inp,
                 InputStatus::Disconnected => 0,
             };
+            // the up and down keys of either side move the paddle of the handle, wherever it plays
+            let up = input & (INPUT_LEFT_PADDLE_UP | INPUT_RIGHT_PADDLE_UP) != 0;
+            let down = input & (INPUT_LEFT_PADDLE_DOWN | INPUT_RIGHT_PADDLE_DOWN) != 0;
+            let paddle = match self.side_of(handle) {
+                Side::Left => &mut self.left_paddle,
+                Side::Right => &mut self.right_paddle,
+            };
+            paddle.vel = match (up, down) {
+                (true, false) => -self.rules.paddle_speed,
+                (false, true) => self.rules.paddle_speed,
+                _ => 0,
+            };
+        }
+
+        self.check_goal();
+    }
+
+    /// Score a point if the ball passed a paddle, and end the set if the scoring paddle won it
+    fn check_goal(&mut self) {
+        let missed = |paddle: &Paddle| self.ball.missed_paddle(paddle.pos, paddle.height);
+        let scorer = if self.ball.pos_x > EDGE_RIGHT && missed(&self.right_paddle) {
+            Side::Left
+        } else if self.ball.pos_x < EDGE_LEFT && missed(&self.left_paddle) {
+            Side::Right
+        } else {
+            return;
+        };
+
+        let (paddle, opponent) = match scorer {
+            Side::Left => (&mut self.left_paddle, &self.right_paddle),
+            Side::Right => (&mut self.right_paddle, &self.left_paddle),
+        };
+        paddle.score_point();
+        if self.rules.set_won(paddle.score, opponent.score) {
+            self.end_set(scorer);
+        }
+        self.ball.reset_position();
+    }
+
+    /// Count the set won by the paddle on the `winner` side, and either end the match
+    /// or start the next set, switching sides if the rules say so
+    fn end_set(&mut self, winner: Side) {
+        self.sets[winner.index()] += 1;
+        if self.sets[winner.index()] >= self.rules.sets_to_win() {
+            self.winner = Some(winner);
+            return;
+        }
+
+        self.left_paddle.score = 0;
+        self.right_paddle.score = 0;
+        if self.rules.switch_sides {
+            self.sets.swap(0, 1);
+            self.sides_switched = !self.sides_switched;
+        }
+    }
 
-            if input & INPUT_LEFT_PADDLE_UP != 0 && input & INPUT_LEFT_PADDLE_DOWN == 0 {
-                self.left_paddle.vel = -2;
-                detected_left = true;
+    /// Record the rematch votes of the players, and restart the match once every player voted
+    fn handle_rematch_votes(&mut self, inputs_vector: &[(Input, InputStatus)]) {
+        for (handle, (input, status)) in inputs_vector.iter().enumerate().take(NUM_PLAYERS) {
+            if !matches!(status, InputStatus::Disconnected) && input.inp & INPUT_REMATCH != 0 {
+                self.rematch_votes[handle] = true;
             }
-            if input & INPUT_LEFT_PADDLE_UP == 0 && input & INPUT_LEFT_PADDLE_DOWN != 0 {
-                self.left_paddle.vel = 2;
-                detected_left = true;
+        }
+
+        if self.rematch_votes.iter().all(|vote| *vote) {
+            self.restart();
+        }
+    }
+
+    /// Pause the match for the first player handle asking for it with pauses left, returning true if it got paused
+    fn handle_pause_requests(&mut self, inputs_vector: &[(Input, InputStatus)]) -> bool {
+        for (handle, (input, status)) in inputs_vector.iter().enumerate().take(NUM_PLAYERS) {
+            if !matches!(status, InputStatus::Disconnected)
+                && input.inp & INPUT_PAUSE != 0
+                && self.pauses_left[handle] > 0
+            {
+                self.pauses_left[handle] -= 1;
+                self.paused_by = Some(handle);
+                self.resume_votes = [false; NUM_PLAYERS];
+                return true;
             }
-            if input & INPUT_RIGHT_PADDLE_UP != 0 && input & INPUT_RIGHT_PADDLE_DOWN == 0 {
-                self.right_paddle.vel = -2;
-                detected_right = true;
+        }
+        false
+    }
+
+    /// Record the resume votes of the players and resume the match once every player voted,
+    /// disconnected players counting as voting. A player forfeiting ends the match, won by the other side.
+    fn handle_pause_votes(&mut self, inputs_vector: &[(Input, InputStatus)]) {
+        for (handle, (input, status)) in inputs_vector.iter().enumerate().take(NUM_PLAYERS) {
+            if matches!(status, InputStatus::Disconnected) {
+                self.resume_votes[handle] = true;
+            } else if input.inp & INPUT_FORFEIT != 0 {
+                self.winner = Some(self.side_of(handle).opposite());
+                self.paused_by = None;
+                return;
+            } else if input.inp & INPUT_RESUME != 0 {
+                self.resume_votes[handle] = true;
             }
-            if input & INPUT_RIGHT_PADDLE_UP == 0 && input & INPUT_RIGHT_PADDLE_DOWN != 0 {
-                self.right_paddle.vel = 2;
-                detected_right = true;
+        }
+
+        if self.resume_votes.iter().all(|vote| *vote) {
+            self.paused_by = None;
+        }
+    }
+
+    /// Reset the match to its initial state, keeping the rules, the frame count and the random source
+    /// so the session stays in sync
+    fn restart(&mut self) {
+        *self = Self {
+            frame: self.frame,
+            rng: self.rng,
+            ..Self::for_match(self.rules, 0)
+        };
+    }
+}
+
+impl StateHash for GameState {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_i32(self.frame);
+        for (frame, checksum) in [self.last_checksum, self.periodic_checksum] {
+            hasher.write_i32(frame);
+            hasher.write_u64(checksum);
+        }
+        hasher.write_u64(self.sound_played as u64);
+        self.left_paddle.hash_state(hasher);
+        self.right_paddle.hash_state(hasher);
+        self.ball.hash_state(hasher);
+        // an option is a 0, or a 1 followed by its value
+        match self.winner {
+            None => hasher.write_u8(0),
+            Some(side) => {
+                hasher.write_u8(1);
+                hasher.write_u8(side.index() as u8);
+            }
+        }
+        // an array is its length followed by its values
+        hasher.write_u64(NUM_PLAYERS as u64);
+        for vote in self.rematch_votes {
+            hasher.write_u8(vote as u8);
+        }
+        self.rng.hash_state(hasher);
+        self.rules.hash_state(hasher);
+        hasher.write_i32(self.sets[0]);
+        hasher.write_i32(self.sets[1]);
+        hasher.write_u8(self.sides_switched as u8);
+        match self.paused_by {
+            None => hasher.write_u8(0),
+            Some(handle) => {
+                hasher.write_u8(1);
+                hasher.write_u64(handle as u64);
             }
         }
+        hasher.write_u64(NUM_PLAYERS as u64);
+        for pauses in self.pauses_left {
+            hasher.write_u8(pauses);
+        }
+        hasher.write_u64(NUM_PLAYERS as u64);
+        for vote in self.resume_votes {
+            hasher.write_u8(vote as u8);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{match_rules::MatchPreset, SCORE_MAX_VALUE};
+
+    fn inputs(first: u8, second: u8) -> Vec<(Input, InputStatus)> {
+        vec![
+            (Input { inp: first }, InputStatus::Confirmed),
+            (Input { inp: second }, InputStatus::Confirmed),
+        ]
+    }
+
+    #[test]
+    fn score_when_ball_passes_paddle() {
+        let mut state = GameState::new();
+        state.ball.pos_x = EDGE_RIGHT + 1;
+        state.ball.pos_y = state.right_paddle.pos + 100;
+        state.check_goal();
+        assert_eq!(state.left_paddle.score, 1);
+        assert_eq!(state.winner, None);
+    }
+
+    #[test]
+    fn win_on_last_point() {
+        let mut state = GameState::new();
+        state.right_paddle.score = SCORE_MAX_VALUE - 1;
+        state.ball.pos_x = EDGE_LEFT - 1;
+        state.ball.pos_y = state.left_paddle.pos + 100;
+        state.check_goal();
+        assert_eq!(state.winner, Some(Side::Right));
+    }
 
-        if !detected_left {
-            self.left_paddle.vel = 0;
+    #[test]
+    fn frozen_after_match_is_over() {
+        let mut state = GameState::new();
+        state.winner = Some(Side::Left);
+        let ball_x = state.ball.pos_x;
+        state.advance(inputs(INPUT_LEFT_PADDLE_DOWN, 0), &[]);
+        assert_eq!(state.ball.pos_x, ball_x);
+        assert_eq!(state.left_paddle.vel, 0);
+    }
+
+    #[test]
+    fn rematch_needs_both_votes() {
+        let mut state = GameState::new();
+        state.left_paddle.score = SCORE_MAX_VALUE;
+        state.winner = Some(Side::Left);
+
+        state.advance(inputs(INPUT_REMATCH, 0), &[]);
+        assert_eq!(state.winner, Some(Side::Left));
+        assert_eq!(state.rematch_votes, [true, false]);
+
+        state.advance(inputs(0, INPUT_REMATCH), &[]);
+        assert_eq!(state.winner, None);
+        assert_eq!(state.left_paddle.score, 0);
+        assert_eq!(state.rematch_votes, [false, false]);
+        assert_eq!(state.frame, 2);
+    }
+
+    #[test]
+    fn score_limit_from_rules() {
+        let rules = MatchRules {
+            score_limit: 1,
+            ..MatchRules::classic()
+        };
+        let mut state = GameState::for_match(rules, 0);
+        state.ball.pos_x = EDGE_RIGHT + 1;
+        state.ball.pos_y = state.right_paddle.pos + 100;
+        state.check_goal();
+        assert_eq!(state.winner, Some(Side::Left));
+    }
+
+    /// Score a point for the paddle on the `scorer` side
+    fn goal(state: &mut GameState, scorer: Side) {
+        match scorer {
+            Side::Left => {
+                state.ball.pos_x = EDGE_RIGHT + 1;
+                state.ball.pos_y = state.right_paddle.pos + 100;
+            }
+            Side::Right => {
+                state.ball.pos_x = EDGE_LEFT - 1;
+                state.ball.pos_y = state.left_paddle.pos + 100;
+            }
         }
+        state.check_goal();
+    }
 
-        if !detected_right {
-            self.right_paddle.vel = 0;
+    #[test]
+    fn deuce_needs_two_points_lead() {
+        let rules = MatchRules {
+            score_limit: 3,
+            win_by_two: true,
+            ..MatchRules::classic()
+        };
+        let mut state = GameState::for_match(rules, 0);
+        for scorer in [Side::Left, Side::Right, Side::Left, Side::Right, Side::Left] {
+            goal(&mut state, scorer);
         }
+        assert_eq!((state.left_paddle.score, state.right_paddle.score), (3, 2));
+        assert_eq!(state.winner, None);
+
+        goal(&mut state, Side::Right);
+        goal(&mut state, Side::Left);
+        assert_eq!(state.winner, None);
+        goal(&mut state, Side::Left);
+        assert_eq!(state.winner, Some(Side::Left));
+    }
+
+    #[test]
+    fn best_of_three_switching_sides() {
+        let rules = MatchRules {
+            score_limit: 1,
+            best_of: 3,
+            switch_sides: true,
+            ..MatchRules::classic()
+        };
+        let mut state = GameState::for_match(rules, 0);
+
+        goal(&mut state, Side::Left);
+        assert_eq!(state.winner, None);
+        // the set winner moved to the right side
+        assert_eq!(state.sets, [0, 1]);
+        assert!(state.sides_switched);
+        assert_eq!(state.left_paddle.score, 0);
+
+        goal(&mut state, Side::Right);
+        assert_eq!(state.sets, [0, 2]);
+        assert_eq!(state.winner, Some(Side::Right));
+        assert_eq!(state.right_paddle.score, 1);
+    }
+
+    #[test]
+    fn set_won_after_switching_sides_is_credited_to_its_player() {
+        let rules = MatchRules {
+            score_limit: 1,
+            best_of: 3,
+            switch_sides: true,
+            ..MatchRules::classic()
+        };
+        let mut state = GameState::for_match(rules, 0);
+        goal(&mut state, Side::Left);
+        assert_eq!(state.side_of(1), Side::Left);
+
+        // the second player moves its paddle, now on the left, out of the way of the ball with the arrow keys
+        state.left_paddle.pos = 100;
+        state.ball.pos_x = EDGE_LEFT + 6;
+        state.ball.pos_y = 101;
+        state.ball.vel_x = -3;
+        state.ball.vel_y = 0;
+        for _ in 0..3 {
+            state.advance(inputs(0, INPUT_RIGHT_PADDLE_DOWN), &[]);
+        }
+        assert_eq!(state.right_paddle.pos, 1);
+        assert_eq!(state.winner, Some(state.side_of(0)));
+        assert_eq!(state.sets[state.side_of(0).index()], 2);
+    }
+
+    #[test]
+    fn rematch_keeps_rules() {
+        let rules = MatchPreset::Fast.rules();
+        let mut state = GameState::for_match(rules, 0);
+        state.winner = Some(Side::Left);
+        state.advance(inputs(INPUT_REMATCH, INPUT_REMATCH), &[]);
+        assert_eq!(state.rules, rules);
+        assert_eq!(state.ball.vel_x, rules.ball_speed);
+        assert_eq!(state.left_paddle.height, rules.paddle_height);
+
+        state.advance(inputs(INPUT_LEFT_PADDLE_DOWN, 0), &[]);
+        assert_eq!(state.left_paddle.vel, rules.paddle_speed);
+    }
+
+    #[test]
+    fn pause_freezes_until_both_resume() {
+        let mut state = GameState::new();
+        state.advance(inputs(0, INPUT_PAUSE), &[]);
+        assert_eq!(state.paused_by, Some(1));
+        assert_eq!(
+            state.pauses_left,
+            [MAX_PAUSES_PER_PLAYER, MAX_PAUSES_PER_PLAYER - 1]
+        );
+
+        let ball_x = state.ball.pos_x;
+        state.advance(inputs(INPUT_RESUME | INPUT_LEFT_PADDLE_DOWN, 0), &[]);
+        assert_eq!(state.ball.pos_x, ball_x);
+        assert_eq!(state.left_paddle.vel, 0);
+        assert_eq!(state.resume_votes, [true, false]);
+
+        state.advance(inputs(0, INPUT_RESUME), &[]);
+        assert_eq!(state.paused_by, None);
+        assert_eq!(state.ball.pos_x, ball_x);
+        state.advance(inputs(0, 0), &[]);
+        assert_ne!(state.ball.pos_x, ball_x);
+        assert_eq!(state.frame, 4);
+    }
+
+    #[test]
+    fn pauses_are_limited() {
+        let mut state = GameState::new();
+        for _ in 0..MAX_PAUSES_PER_PLAYER {
+            state.advance(inputs(INPUT_PAUSE, 0), &[]);
+            assert_eq!(state.paused_by, Some(0));
+            state.advance(inputs(INPUT_RESUME, INPUT_RESUME), &[]);
+        }
+        state.advance(inputs(INPUT_PAUSE, 0), &[]);
+        assert_eq!(state.paused_by, None);
+        assert_eq!(state.pauses_left, [0, MAX_PAUSES_PER_PLAYER]);
+    }
+
+    #[test]
+    fn forfeit_while_paused() {
+        let mut state = GameState::new();
+        state.sides_switched = true;
+        state.advance(inputs(INPUT_PAUSE, 0), &[]);
+        state.advance(inputs(INPUT_FORFEIT, 0), &[]);
+        // the first player plays on the right side after switching sides
+        assert_eq!(state.winner, Some(Side::Left));
+        assert_eq!(state.paused_by, None);
+    }
+
+    #[test]
+    fn disconnected_player_does_not_block_resume() {
+        let mut state = GameState::new();
+        state.advance(inputs(INPUT_PAUSE, 0), &[]);
+        state.advance(
+            vec![
+                (Input { inp: INPUT_RESUME }, InputStatus::Confirmed),
+                (Input { inp: 0 }, InputStatus::Disconnected),
+            ],
+            &[],
+        );
+        assert_eq!(state.paused_by, None);
+    }
+
+    #[test]
+    fn random_sequence_survives_save_and_load() {
+        let mut state = GameState::for_match(MatchRules::classic(), 1234);
+        state.rng.next_u64();
+        let cell = GameStateCell::default();
+        state.handle_requests(
+            vec![GGRSRequest::SaveGameState {
+                cell: cell.clone(),
+                frame: state.frame,
+            }],
+            &[],
+            None,
+        );
+        let expected: Vec<i32> = (0..10).map(|_| state.rng.range(0, 100)).collect();
+
+        state.handle_requests(
+            vec![GGRSRequest::LoadGameState {
+                cell,
+                frame: state.frame,
+            }],
+            &[],
+            None,
+        );
+        let replayed: Vec<i32> = (0..10).map(|_| state.rng.range(0, 100)).collect();
+        assert_eq!(replayed, expected);
+    }
+
+    #[test]
+    fn rematch_keeps_random_sequence() {
+        let mut state = GameState::for_match(MatchRules::classic(), 99);
+        let mut expected = state.rng;
+        state.winner = Some(Side::Right);
+        state.advance(inputs(INPUT_REMATCH, INPUT_REMATCH), &[]);
+        assert_eq!(state.winner, None);
+        assert_eq!(state.rng.next_u64(), expected.next_u64());
+    }
+
+    fn every_field_set() -> GameState {
+        let mut state = GameState::for_match(MatchPreset::Fast.rules(), 0xDEAD_BEEF);
+        state.frame = 1234;
+        state.last_checksum = (1200, 0x0123_4567_89AB_CDEF);
+        state.periodic_checksum = (1140, u64::MAX);
+        state.sound_played = 7;
+        state.left_paddle.score = 2;
+        state.left_paddle.vel = -2;
+        state.right_paddle.pos = 120;
+        state.ball.pos_x = 42;
+        state.ball.vel_y = -3;
+        state.ball.changed_direction = true;
+        state.winner = Some(Side::Right);
+        state.rematch_votes = [true, false];
+        state.rng.next_u64();
+        state.rules.score_cap = Some(15);
+        state.sets = [1, -1];
+        state.sides_switched = true;
+        state.paused_by = Some(1);
+        state.pauses_left = [2, 0];
+        state.resume_votes = [false, true];
+        state
+    }
+
+    #[test]
+    fn pauses_change_checksum() {
+        let state = GameState::new();
+        let mut used_pause = state;
+        used_pause.pauses_left[1] -= 1;
+        assert_ne!(used_pause.checksum(), state.checksum());
+        let mut voted = state;
+        voted.resume_votes[0] = true;
+        assert_ne!(voted.checksum(), state.checksum());
+    }
+
+    #[test]
+    fn checksums_are_stable() {
+        // the recorded replays hold these checksums, they must not change between builds
+        assert_eq!(GameState::new().checksum(), 0x48a2_fc9e_24be_e887);
+        assert_eq!(every_field_set().checksum(), 0xf565_c231_ddc7_4cce);
     }
 }
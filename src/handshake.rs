@@ -0,0 +1,566 @@
+use std::{collections::HashMap, hash::Hasher};
+
+use ggrs::{Message, NonBlockingSocket, PlayerType};
+use instant::{Duration, Instant};
+use macroquad::logging::warn;
+use matchbox_socket::WebRtcSocket;
+
+use crate::{
+    helpers::StateHasher,
+    match_rules::{MatchPreset, MatchRules},
+    HANDSHAKE_HELLO_INTERVAL, PING_PROBE_INTERVAL, PING_PROBE_SAMPLES, PING_PROBE_TIMEOUT,
+    PLAYER_NAME_MAX_LENGTH,
+};
+
+/// Prefix of every handshake packet, so they can be told apart from GGRS messages
+const HANDSHAKE_MAGIC: &[u8; 8] = b"GGRPHAND";
+const PACKET_HELLO: u8 = 0;
+const PACKET_PING: u8 = 1;
+const PACKET_PONG: u8 = 2;
+const PACKET_RULES: u8 = 3;
+const PACKET_NAME: u8 = 4;
+const PACKET_STARTED: u8 = 5;
+
+/// Role a peer takes in a match
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeerRole {
+    /// Plays the match with one of the paddles
+    Player,
+    /// Watches the match of the players
+    Spectator,
+}
+
+/// A packet exchanged with the other peers of the room before the match starts
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum HandshakePacket {
+    /// Announce the role of the sender
+    Hello(PeerRole),
+    Ping(u32),
+    Pong(u32),
+    /// Announce the rules the sender would host the match with
+    Rules(MatchPreset),
+    /// Announce the display name of the sender
+    Name(String),
+    /// Tell a spectator that the match started without it
+    Started,
+}
+
+impl HandshakePacket {
+    fn encode(&self) -> Box<[u8]> {
+        let (kind, value) = match self {
+            HandshakePacket::Hello(PeerRole::Player) => (PACKET_HELLO, 0),
+            HandshakePacket::Hello(PeerRole::Spectator) => (PACKET_HELLO, 1),
+            HandshakePacket::Ping(seq) => (PACKET_PING, *seq),
+            HandshakePacket::Pong(seq) => (PACKET_PONG, *seq),
+            HandshakePacket::Rules(preset) => (PACKET_RULES, preset.index()),
+            HandshakePacket::Started => (PACKET_STARTED, 0),
+            // names are the only packets with a variable length
+            HandshakePacket::Name(name) => {
+                let mut packet = HANDSHAKE_MAGIC.to_vec();
+                packet.push(PACKET_NAME);
+                packet.extend_from_slice(name.as_bytes());
+                return packet.into_boxed_slice();
+            }
+        };
+        let mut packet = HANDSHAKE_MAGIC.to_vec();
+        packet.push(kind);
+        packet.extend_from_slice(&value.to_le_bytes());
+        packet.into_boxed_slice()
+    }
+
+    /// Return the handshake packet contained in `packet`, or `None` if it is not a handshake packet
+    fn decode(packet: &[u8]) -> Option<Self> {
+        let body = packet.strip_prefix(HANDSHAKE_MAGIC.as_slice())?;
+        let (&kind, value) = body.split_first()?;
+        if kind == PACKET_NAME {
+            let name = String::from_utf8(value.to_vec()).ok()?;
+            return Some(HandshakePacket::Name(
+                name.chars().take(PLAYER_NAME_MAX_LENGTH).collect(),
+            ));
+        }
+        let value = u32::from_le_bytes(value.try_into().ok()?);
+        match (kind, value) {
+            (PACKET_HELLO, 0) => Some(HandshakePacket::Hello(PeerRole::Player)),
+            (PACKET_HELLO, 1) => Some(HandshakePacket::Hello(PeerRole::Spectator)),
+            (PACKET_PING, seq) => Some(HandshakePacket::Ping(seq)),
+            (PACKET_PONG, seq) => Some(HandshakePacket::Pong(seq)),
+            (PACKET_RULES, index) => MatchPreset::from_index(index).map(HandshakePacket::Rules),
+            (PACKET_STARTED, 0) => Some(HandshakePacket::Started),
+            _ => None,
+        }
+    }
+}
+
+/// Answer a handshake ping with a pong.
+/// Return true if `packet` was a handshake packet and should not be handled any further.
+fn answer_ping(socket: &mut WebRtcSocket, peer: &str, packet: &[u8]) -> bool {
+    match HandshakePacket::decode(packet) {
+        Some(HandshakePacket::Ping(seq)) => {
+            socket.send(HandshakePacket::Pong(seq).encode(), peer.to_owned());
+            true
+        }
+        Some(_) => true,
+        None => false,
+    }
+}
+
+/// Measures the round trip time to the other player before the GGRS session is started
+pub struct PingProbe {
+    /// Round trip times measured so far
+    samples: Vec<Duration>,
+    /// Pings waiting for a pong, by sequence number
+    in_flight: HashMap<u32, Instant>,
+    /// Sequence number of the next ping
+    next_seq: u32,
+    /// When the last ping was sent
+    last_sent: Option<Instant>,
+    /// When the probe started
+    started: Instant,
+}
+
+impl PingProbe {
+    pub fn new() -> Self {
+        Self {
+            samples: Vec::new(),
+            in_flight: HashMap::new(),
+            next_seq: 0,
+            last_sent: None,
+            started: Instant::now(),
+        }
+    }
+
+    /// Return true once [`PING_PROBE_SAMPLES`] pongs were received or [`PING_PROBE_TIMEOUT`] has passed
+    pub fn is_done(&self) -> bool {
+        self.samples.len() >= PING_PROBE_SAMPLES
+            || Instant::now().duration_since(self.started) >= PING_PROBE_TIMEOUT
+    }
+
+    /// Return the median of the measured round trip times in milliseconds, or `None` if no pong was received
+    pub fn median_ping_ms(&self) -> Option<u128> {
+        let mut samples = self.samples.clone();
+        samples.sort();
+        samples.get(samples.len() / 2).map(|ping| ping.as_millis())
+    }
+
+    /// Send a ping to `peers` once every [`PING_PROBE_INTERVAL`]
+    fn send_pings(&mut self, socket: &mut WebRtcSocket, peers: &[String], now: Instant) {
+        let interval_passed = self
+            .last_sent
+            .is_none_or(|sent| now.duration_since(sent) >= PING_PROBE_INTERVAL);
+        if !interval_passed || self.is_done() {
+            return;
+        }
+
+        let ping = HandshakePacket::Ping(self.next_seq).encode();
+        for peer in peers {
+            socket.send(ping.clone(), peer.clone());
+        }
+        self.in_flight.insert(self.next_seq, now);
+        self.next_seq += 1;
+        self.last_sent = Some(now);
+    }
+
+    fn record_pong(&mut self, seq: u32, now: Instant) {
+        if let Some(sent) = self.in_flight.remove(&seq) {
+            self.samples.push(now.duration_since(sent));
+        }
+    }
+}
+
+impl Default for PingProbe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Exchanges roles, rules and pings with the other peers of the room before the GGRS session is started
+pub struct Handshake {
+    /// Role of the local peer
+    role: PeerRole,
+    /// Rules the local peer would host the match with
+    preset: MatchPreset,
+    /// Display name of the local peer
+    name: String,
+    /// Roles announced by the connected peers
+    peers: HashMap<String, PeerRole>,
+    /// Rules announced by the connected players
+    presets: HashMap<String, MatchPreset>,
+    /// Display names announced by the connected players
+    names: HashMap<String, String>,
+    /// When the local role was last announced
+    last_hello: Option<Instant>,
+    /// Ping measurement, if one was started
+    probe: Option<PingProbe>,
+    /// True if the host told the local spectator that the match started without it
+    refused: bool,
+}
+
+impl Handshake {
+    pub fn new(role: PeerRole, preset: MatchPreset, name: String) -> Self {
+        Self {
+            role,
+            preset,
+            name,
+            peers: HashMap::new(),
+            presets: HashMap::new(),
+            names: HashMap::new(),
+            last_hello: None,
+            probe: None,
+            refused: false,
+        }
+    }
+
+    /// Role of the local peer
+    pub fn role(&self) -> PeerRole {
+        self.role
+    }
+
+    /// Handle the handshake packets received from the other peers, and announce the local role to them
+    pub fn update(&mut self, socket: &mut WebRtcSocket) {
+        let now = Instant::now();
+        let connected = socket.connected_peers();
+        self.peers.retain(|peer, _| connected.contains(peer));
+        self.presets.retain(|peer, _| connected.contains(peer));
+        self.names.retain(|peer, _| connected.contains(peer));
+
+        for (peer, packet) in socket.receive() {
+            match HandshakePacket::decode(&packet) {
+                Some(HandshakePacket::Hello(role)) => {
+                    self.peers.insert(peer, role);
+                }
+                Some(HandshakePacket::Rules(preset)) => {
+                    self.presets.insert(peer, preset);
+                }
+                Some(HandshakePacket::Name(name)) => {
+                    self.names.insert(peer, name);
+                }
+                Some(HandshakePacket::Pong(seq)) => {
+                    if let Some(probe) = &mut self.probe {
+                        probe.record_pong(seq, now);
+                    }
+                }
+                Some(HandshakePacket::Started) if self.role == PeerRole::Spectator => {
+                    self.refused = true;
+                }
+                _ => {
+                    answer_ping(socket, &peer, &packet);
+                }
+            }
+        }
+
+        let hello_due = self
+            .last_hello
+            .is_none_or(|sent| now.duration_since(sent) >= HANDSHAKE_HELLO_INTERVAL);
+        if hello_due {
+            let hello = HandshakePacket::Hello(self.role).encode();
+            let rules = HandshakePacket::Rules(self.preset).encode();
+            let name = HandshakePacket::Name(self.name.clone()).encode();
+            for peer in &connected {
+                socket.send(hello.clone(), peer.clone());
+                if self.role == PeerRole::Player {
+                    socket.send(rules.clone(), peer.clone());
+                    socket.send(name.clone(), peer.clone());
+                }
+            }
+            self.last_hello = Some(now);
+        }
+
+        let players = self.remote_ids(PeerRole::Player);
+        if let Some(probe) = &mut self.probe {
+            probe.send_pings(socket, &players, now);
+        }
+    }
+
+    /// Start measuring the ping to the other players, if not started yet, and return the measurement
+    pub fn probe(&mut self) -> &PingProbe {
+        self.probe.get_or_insert_with(PingProbe::new)
+    }
+
+    /// Return true if the local peer is a spectator that joined after the match started, which it can not watch
+    pub fn is_refused(&self) -> bool {
+        self.refused
+    }
+
+    /// Return true if a ping measurement is in progress
+    pub fn is_probing(&self) -> bool {
+        self.probe.as_ref().is_some_and(|probe| !probe.is_done())
+    }
+
+    /// Return the sorted ids of the connected peers that announced `role`
+    pub fn remote_ids(&self, role: PeerRole) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .peers
+            .iter()
+            .filter(|(_, peer_role)| **peer_role == role)
+            .map(|(peer, _)| peer.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Return the players of the match ordered by their handle, which is consistent across all peers
+    pub fn players(&self, local_id: &str) -> Vec<PlayerType<String>> {
+        let mut ids = self.remote_ids(PeerRole::Player);
+        if self.role == PeerRole::Player {
+            ids.push(local_id.to_owned());
+            ids.sort();
+        }
+        ids.into_iter()
+            .map(|id| {
+                if id == local_id && self.role == PeerRole::Player {
+                    PlayerType::Local
+                } else {
+                    PlayerType::Remote(id)
+                }
+            })
+            .collect()
+    }
+
+    /// Return the ids of the players ordered by their handle
+    pub fn player_ids(&self, local_id: &str) -> Vec<String> {
+        self.players(local_id)
+            .into_iter()
+            .map(|player| match player {
+                PlayerType::Remote(id) | PlayerType::Spectator(id) => id,
+                PlayerType::Local => local_id.to_owned(),
+            })
+            .collect()
+    }
+
+    /// Return the display names of the players ordered by their handle,
+    /// with a default name for the players that did not announce one yet
+    pub fn player_names(&self, local_id: &str) -> Vec<String> {
+        self.players(local_id)
+            .into_iter()
+            .enumerate()
+            .map(|(handle, player)| {
+                let name = match player {
+                    PlayerType::Local => Some(self.name.clone()),
+                    PlayerType::Remote(id) | PlayerType::Spectator(id) => {
+                        self.names.get(&id).cloned()
+                    }
+                };
+                name.unwrap_or_else(|| format!("Player {}", handle + 1))
+            })
+            .collect()
+    }
+
+    /// Return the seed of the match's random source, derived from the ids the signalling server gave to the players,
+    /// so every player and spectator computes the same seed without exchanging anything else
+    pub fn match_seed(&self, local_id: &str) -> u64 {
+        let ids = self.player_ids(local_id);
+        let mut hasher = StateHasher::new();
+        hasher.write_u64(ids.len() as u64);
+        for id in ids {
+            // the end marker keeps ["ab", "c"] and ["a", "bc"] apart
+            hasher.write(id.as_bytes());
+            hasher.write_u8(0xff);
+        }
+        hasher.finish()
+    }
+
+    /// Return the rules of the match, which are the ones of the player with handle 0,
+    /// or `None` if that player did not announce them yet
+    pub fn match_rules(&self, local_id: &str) -> Option<MatchRules> {
+        let host = self.host_id(local_id)?;
+        if host == local_id && self.role == PeerRole::Player {
+            Some(self.preset.rules())
+        } else {
+            self.presets.get(&host).map(|preset| preset.rules())
+        }
+    }
+
+    /// Return the id of the player hosting the spectators, which is the player with handle 0
+    pub fn host_id(&self, local_id: &str) -> Option<String> {
+        match self.players(local_id).into_iter().next()? {
+            PlayerType::Local => Some(local_id.to_owned()),
+            PlayerType::Remote(id) => Some(id),
+            PlayerType::Spectator(_) => None,
+        }
+    }
+}
+
+/// [`WebRtcSocket`] wrapper handed to GGRS, which answers the late pings of a peer still in the handshake
+/// and keeps handshake packets from reaching GGRS
+pub struct HandshakeAwareSocket {
+    socket: WebRtcSocket,
+    /// Spectators of the session if the local player hosts them, the ones arriving later are refused
+    hosted_spectators: Option<Vec<String>>,
+}
+
+impl HandshakeAwareSocket {
+    pub fn new(socket: WebRtcSocket) -> Self {
+        Self {
+            socket,
+            hosted_spectators: None,
+        }
+    }
+
+    /// Wrap the socket of the player hosting `spectators`,
+    /// telling the spectators that announce themselves later that the match started without them
+    pub fn hosting(socket: WebRtcSocket, spectators: Vec<String>) -> Self {
+        Self {
+            socket,
+            hosted_spectators: Some(spectators),
+        }
+    }
+
+    /// Refuse `peer` if `packet` announces a spectator the session does not host.
+    /// Return true if `packet` was a handshake packet and should not be handled any further.
+    fn refuse_late_spectator(&mut self, peer: &str, packet: &[u8]) -> bool {
+        let Some(spectators) = &self.hosted_spectators else {
+            return false;
+        };
+        if HandshakePacket::decode(packet) != Some(HandshakePacket::Hello(PeerRole::Spectator)) {
+            return false;
+        }
+        if !spectators.iter().any(|spectator| spectator == peer) {
+            self.socket
+                .send(HandshakePacket::Started.encode(), peer.to_owned());
+        }
+        true
+    }
+}
+
+impl NonBlockingSocket<String> for HandshakeAwareSocket {
+    fn send_to(&mut self, msg: &Message, addr: &String) {
+        self.socket.send_to(msg, addr);
+    }
+
+    fn receive_all_messages(&mut self) -> Vec<(String, Message)> {
+        let mut messages = Vec::new();
+        for (peer, packet) in self.socket.receive() {
+            if self.refuse_late_spectator(&peer, &packet)
+                || answer_ping(&mut self.socket, &peer, &packet)
+            {
+                continue;
+            }
+            match bincode::deserialize(&packet) {
+                Ok(msg) => messages.push((peer, msg)),
+                Err(e) => warn!("Dropping invalid packet from {peer}: {e}"),
+            }
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packet_round_trip() {
+        for packet in [
+            HandshakePacket::Hello(PeerRole::Player),
+            HandshakePacket::Hello(PeerRole::Spectator),
+            HandshakePacket::Ping(42),
+            HandshakePacket::Pong(7),
+            HandshakePacket::Rules(MatchPreset::Long),
+            HandshakePacket::Name("Zoë".to_owned()),
+            HandshakePacket::Name(String::new()),
+            HandshakePacket::Started,
+        ] {
+            assert_eq!(HandshakePacket::decode(&packet.encode()), Some(packet));
+        }
+    }
+
+    #[test]
+    fn ignore_other_packets() {
+        assert_eq!(HandshakePacket::decode(&[0, 1, 2, 3]), None);
+        assert_eq!(HandshakePacket::decode(HANDSHAKE_MAGIC), None);
+        let mut unknown_preset = HANDSHAKE_MAGIC.to_vec();
+        unknown_preset.push(PACKET_RULES);
+        unknown_preset.extend(99u32.to_le_bytes());
+        assert_eq!(HandshakePacket::decode(&unknown_preset), None);
+        let mut invalid_name = HANDSHAKE_MAGIC.to_vec();
+        invalid_name.push(PACKET_NAME);
+        invalid_name.extend([0xff, 0xfe]);
+        assert_eq!(HandshakePacket::decode(&invalid_name), None);
+    }
+
+    #[test]
+    fn long_names_are_truncated() {
+        let long_name = "x".repeat(PLAYER_NAME_MAX_LENGTH * 2);
+        let decoded = HandshakePacket::decode(&HandshakePacket::Name(long_name).encode());
+        assert_eq!(
+            decoded,
+            Some(HandshakePacket::Name("x".repeat(PLAYER_NAME_MAX_LENGTH)))
+        );
+    }
+
+    #[test]
+    fn names_ordered_by_handle() {
+        let mut handshake =
+            Handshake::new(PeerRole::Player, MatchPreset::Classic, "Bea".to_owned());
+        handshake.peers.insert("a".to_owned(), PeerRole::Player);
+        assert_eq!(handshake.player_names("b"), vec!["Player 1", "Bea"]);
+        handshake.names.insert("a".to_owned(), "Ari".to_owned());
+        assert_eq!(handshake.player_names("b"), vec!["Ari", "Bea"]);
+    }
+
+    #[test]
+    fn players_ordered_by_id() {
+        let mut handshake = Handshake::new(PeerRole::Player, MatchPreset::Classic, "a".to_owned());
+        handshake.peers.insert("a".to_owned(), PeerRole::Player);
+        handshake.peers.insert("0".to_owned(), PeerRole::Spectator);
+        assert_eq!(
+            handshake.players("b"),
+            vec![PlayerType::Remote("a".to_owned()), PlayerType::Local]
+        );
+        assert_eq!(handshake.host_id("b"), Some("a".to_owned()));
+    }
+
+    #[test]
+    fn spectator_is_not_a_player() {
+        let mut handshake =
+            Handshake::new(PeerRole::Spectator, MatchPreset::Classic, "s".to_owned());
+        handshake.peers.insert("b".to_owned(), PeerRole::Player);
+        handshake.peers.insert("c".to_owned(), PeerRole::Player);
+        assert_eq!(
+            handshake.players("a"),
+            vec![
+                PlayerType::Remote("b".to_owned()),
+                PlayerType::Remote("c".to_owned())
+            ]
+        );
+        assert_eq!(handshake.host_id("a"), Some("b".to_owned()));
+    }
+
+    #[test]
+    fn host_picks_the_rules() {
+        let mut host = Handshake::new(PeerRole::Player, MatchPreset::Fast, "a".to_owned());
+        host.peers.insert("b".to_owned(), PeerRole::Player);
+        host.presets.insert("b".to_owned(), MatchPreset::Long);
+        assert_eq!(host.match_rules("a"), Some(MatchPreset::Fast.rules()));
+
+        let mut guest = Handshake::new(PeerRole::Player, MatchPreset::Long, "b".to_owned());
+        guest.peers.insert("a".to_owned(), PeerRole::Player);
+        assert_eq!(guest.match_rules("b"), None);
+        guest.presets.insert("a".to_owned(), MatchPreset::Fast);
+        assert_eq!(guest.match_rules("b"), Some(MatchPreset::Fast.rules()));
+    }
+
+    #[test]
+    fn same_seed_on_every_peer() {
+        let mut first = Handshake::new(PeerRole::Player, MatchPreset::Classic, "a".to_owned());
+        first.peers.insert("b".to_owned(), PeerRole::Player);
+        first.peers.insert("c".to_owned(), PeerRole::Spectator);
+        let mut second = Handshake::new(PeerRole::Player, MatchPreset::Classic, "a".to_owned());
+        second.peers.insert("a".to_owned(), PeerRole::Player);
+        let mut spectator =
+            Handshake::new(PeerRole::Spectator, MatchPreset::Classic, "s".to_owned());
+        spectator.peers.insert("b".to_owned(), PeerRole::Player);
+        spectator.peers.insert("a".to_owned(), PeerRole::Player);
+
+        let seed = first.match_seed("a");
+        // the seed must not change between builds, or the replays would not play back the same
+        assert_eq!(seed, 0x23f8_9a21_815e_7a07);
+        assert_eq!(second.match_seed("b"), seed);
+        assert_eq!(spectator.match_seed("c"), seed);
+
+        let mut other_match =
+            Handshake::new(PeerRole::Player, MatchPreset::Classic, "a".to_owned());
+        other_match.peers.insert("d".to_owned(), PeerRole::Player);
+        assert_ne!(other_match.match_seed("a"), seed);
+    }
+}
@@ -0,0 +1,80 @@
+use std::process::ExitCode;
+
+use crate::{
+    cpu::CpuLevel,
+    game_state::GameState,
+    launch::{LaunchConfig, LaunchMode},
+    local_play::{Controller, LocalDriver},
+    match_rules::MatchPreset,
+    match_screen::MatchDriver,
+    replay::Replay,
+    session_settings::SessionSettings,
+    sync_test::SyncTestDriver,
+    HEADLESS_DEFAULT_SEED, HEADLESS_MAX_FRAMES, NUM_PLAYERS,
+};
+
+/// Run the match of the `launch` configuration without a window, as fast as possible, and print how it ended.
+/// Local matches are played between computer-controlled players, of the level given with `--vs-cpu` or medium,
+/// and seeded with `--seed` or [`HEADLESS_DEFAULT_SEED`] so they end the same on every run.
+pub fn run(launch: &LaunchConfig, settings: &SessionSettings) -> ExitCode {
+    let level = match launch.mode {
+        LaunchMode::Menu | LaunchMode::Host | LaunchMode::Join(_) => {
+            eprintln!("Only --local, --vs-cpu, --synctest and --replay can run headless");
+            return ExitCode::from(2);
+        }
+        LaunchMode::Replay(ref path) => {
+            return match Replay::load(path) {
+                Ok(replay) => run_replay(&replay),
+                Err(e) => {
+                    eprintln!("Could not load replay {}: {e}", path.display());
+                    ExitCode::from(2)
+                }
+            }
+        }
+        LaunchMode::VsCpu(level) => level,
+        LaunchMode::Local | LaunchMode::SyncTest => CpuLevel::Medium,
+    };
+
+    let controllers = [Controller::Cpu(level); NUM_PLAYERS];
+    let mut driver: Box<dyn MatchDriver> = match launch.mode {
+        LaunchMode::SyncTest => Box::new(SyncTestDriver::new(controllers, settings)),
+        _ => Box::new(LocalDriver::new(controllers)),
+    };
+    let seed = launch.seed.unwrap_or(HEADLESS_DEFAULT_SEED);
+    let mut state = GameState::for_match(MatchPreset::default().rules(), seed);
+    while state.winner.is_none() && (state.frame() as usize) < HEADLESS_MAX_FRAMES {
+        driver.advance(&mut state, 0, [0; NUM_PLAYERS], &[]);
+    }
+
+    print_result(&state);
+    if state.winner.is_none() {
+        println!("no winner after {HEADLESS_MAX_FRAMES} frames");
+    }
+    ExitCode::SUCCESS
+}
+
+/// Simulate the `replay` and compare the result with its stored checksums,
+/// failing when it has none, as nothing could be verified
+fn run_replay(replay: &Replay) -> ExitCode {
+    let run = replay.simulate();
+    print_result(&run.state);
+    match replay.verify(&run) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn print_result(state: &GameState) {
+    println!("frames: {}", state.frame());
+    println!(
+        "score: {} - {}",
+        state.left_paddle.score, state.right_paddle.score
+    );
+    if let Some(winner) = state.winner {
+        println!("winner: {winner:?}");
+    }
+    println!("checksum: {:#018x}", state.checksum());
+}
@@ -1,3 +1,5 @@
+use std::hash::Hasher;
+
 /// Computes the fletcher16 checksum  
 /// Implemented according to wikipedia: <https://en.wikipedia.org/wiki/Fletcher%27s_checksum>
 pub fn fletcher16(data: &[u8]) -> u16 {
@@ -11,3 +13,128 @@ pub fn fletcher16(data: &[u8]) -> u16 {
 
     (sum2 << 8) | sum1
 }
+
+const STATE_HASHER_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+const STATE_HASHER_MULTIPLIER: u64 = 0x5851_F42D_4C95_7F2D;
+
+/// Fast 64 bits non-cryptographic hasher used for the game state checksums  
+/// Every written value is mixed in as a whole 64 bits word, independently of the platform's endianness and
+/// pointer width, and the result goes through the splitmix64 finalizer so a single bit change spreads over
+/// the whole checksum: <https://prng.di.unimi.it/splitmix64.c>
+#[derive(Clone, Copy, Debug)]
+pub struct StateHasher {
+    state: u64,
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self {
+            state: STATE_HASHER_SEED,
+        }
+    }
+
+    fn mix(&mut self, word: u64) {
+        self.state = (self.state ^ word)
+            .wrapping_mul(STATE_HASHER_MULTIPLIER)
+            .rotate_left(29);
+    }
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StateHasher {
+    fn finish(&self) -> u64 {
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.mix(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.mix(i as u64);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.mix(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.mix(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.mix(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.mix(i as u64);
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        self.mix(i as i64 as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.mix(i as i64 as u64);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.mix(i as i64 as u64);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.mix(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.mix(i as i64 as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hash;
+
+    use super::*;
+
+    fn hash(value: impl Hash) -> u64 {
+        let mut hasher = StateHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn deterministic() {
+        assert_eq!(hash((1i32, -2i32, true)), hash((1i32, -2i32, true)));
+    }
+
+    #[test]
+    fn single_bit_changes_checksum() {
+        assert_ne!(hash([0u64, 0, 0]), hash([0u64, 1, 0]));
+        assert_ne!(hash(0i32), hash(1i32));
+    }
+
+    #[test]
+    fn field_order_changes_checksum() {
+        assert_ne!(hash((1i32, 2i32)), hash((2i32, 1i32)));
+    }
+
+    #[test]
+    fn no_fletcher16_collision() {
+        // fletcher16 sums modulo 255, so it can not tell 0x00 and 0xFF bytes apart
+        assert_eq!(fletcher16(&[0x00]), fletcher16(&[0xFF]));
+        assert_ne!(hash(0x00u8), hash(0xFFu8));
+    }
+}
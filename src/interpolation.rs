@@ -0,0 +1,115 @@
+use macroquad::math::{vec2, Vec2};
+
+use crate::{game_state::GameState, INTERPOLATION_MAX_DISTANCE};
+
+/// Positions the moving objects are drawn at, in between two simulated frames.
+/// Only used for rendering, the simulation itself never sees them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderPositions {
+    pub left_paddle: f32,
+    pub right_paddle: f32,
+    pub ball: Vec2,
+}
+
+impl RenderPositions {
+    /// Return the positions of `state`, without interpolation
+    pub fn of(state: &GameState) -> Self {
+        Self {
+            left_paddle: state.left_paddle.pos as f32,
+            right_paddle: state.right_paddle.pos as f32,
+            ball: vec2(state.ball.pos_x as f32, state.ball.pos_y as f32),
+        }
+    }
+
+    /// Return the positions `alpha` of the way from `previous` to `current`, `alpha` being the fraction
+    /// of a frame elapsed since `current` was simulated.
+    /// Snap to `current` if it does not directly follow `previous`, or for objects that teleported, like the ball after a goal.
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::{game_state::GameState, interpolation::RenderPositions};
+    /// let previous = GameState::new();
+    /// let mut current = previous;
+    /// current.advance(Vec::new(), &[]);
+    /// let halfway = RenderPositions::interpolate(&previous, &current, 0.5);
+    /// assert_eq!(halfway.ball.x, (previous.ball.pos_x + current.ball.pos_x) as f32 / 2.0);
+    /// ```
+    pub fn interpolate(previous: &GameState, current: &GameState, alpha: f32) -> Self {
+        if current.frame() != previous.frame() + 1 {
+            return Self::of(current);
+        }
+
+        let alpha = alpha.clamp(0.0, 1.0);
+        Self {
+            left_paddle: lerp(previous.left_paddle.pos, current.left_paddle.pos, alpha),
+            right_paddle: lerp(previous.right_paddle.pos, current.right_paddle.pos, alpha),
+            ball: vec2(
+                lerp(previous.ball.pos_x, current.ball.pos_x, alpha),
+                lerp(previous.ball.pos_y, current.ball.pos_y, alpha),
+            ),
+        }
+    }
+}
+
+/// Interpolate between two positions, or snap to `to` if they are more than [`INTERPOLATION_MAX_DISTANCE`] apart
+fn lerp(from: i32, to: i32, alpha: f32) -> f32 {
+    if (to - from).abs() > INTERPOLATION_MAX_DISTANCE {
+        to as f32
+    } else {
+        from as f32 + (to - from) as f32 * alpha
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MIDDLE_POS;
+
+    fn next_frame(state: &GameState) -> GameState {
+        let mut next = *state;
+        next.advance(Vec::new(), &[]);
+        next
+    }
+
+    #[test]
+    fn interpolate_between_frames() {
+        let mut previous = GameState::new();
+        previous.left_paddle.vel = 2;
+        let current = next_frame(&previous);
+        assert_eq!(
+            RenderPositions::interpolate(&previous, &current, 0.0),
+            RenderPositions::of(&previous)
+        );
+        assert_eq!(
+            RenderPositions::interpolate(&previous, &current, 1.0),
+            RenderPositions::of(&current)
+        );
+        let quarter = RenderPositions::interpolate(&previous, &current, 0.25);
+        assert_eq!(
+            quarter.ball.y,
+            previous.ball.pos_y as f32 + 0.25 * previous.ball.vel_y as f32
+        );
+    }
+
+    #[test]
+    fn snap_when_frames_are_skipped() {
+        let previous = GameState::new();
+        let current = next_frame(&next_frame(&previous));
+        assert_eq!(
+            RenderPositions::interpolate(&previous, &current, 0.5),
+            RenderPositions::of(&current)
+        );
+    }
+
+    #[test]
+    fn snap_ball_reset_after_goal() {
+        let previous = GameState::new();
+        let mut current = next_frame(&previous);
+        current.ball.pos_x = MIDDLE_POS[0];
+        current.ball.pos_y = MIDDLE_POS[1];
+        let positions = RenderPositions::interpolate(&previous, &current, 0.5);
+        assert_eq!(
+            positions.ball,
+            vec2(MIDDLE_POS[0] as f32, MIDDLE_POS[1] as f32)
+        );
+    }
+}
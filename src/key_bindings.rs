@@ -0,0 +1,221 @@
+use macroquad::prelude::KeyCode;
+use serde::{Deserialize, Serialize};
+
+/// Keys that can be bound to an action, by the name they are saved with in the settings file
+const KEY_NAMES: [(&str, KeyCode); 48] = [
+    ("A", KeyCode::A),
+    ("B", KeyCode::B),
+    ("C", KeyCode::C),
+    ("D", KeyCode::D),
+    ("E", KeyCode::E),
+    ("F", KeyCode::F),
+    ("G", KeyCode::G),
+    ("H", KeyCode::H),
+    ("I", KeyCode::I),
+    ("J", KeyCode::J),
+    ("K", KeyCode::K),
+    ("L", KeyCode::L),
+    ("M", KeyCode::M),
+    ("N", KeyCode::N),
+    ("O", KeyCode::O),
+    ("P", KeyCode::P),
+    ("Q", KeyCode::Q),
+    ("R", KeyCode::R),
+    ("S", KeyCode::S),
+    ("T", KeyCode::T),
+    ("U", KeyCode::U),
+    ("V", KeyCode::V),
+    ("W", KeyCode::W),
+    ("X", KeyCode::X),
+    ("Y", KeyCode::Y),
+    ("Z", KeyCode::Z),
+    ("0", KeyCode::Key0),
+    ("1", KeyCode::Key1),
+    ("2", KeyCode::Key2),
+    ("3", KeyCode::Key3),
+    ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5),
+    ("6", KeyCode::Key6),
+    ("7", KeyCode::Key7),
+    ("8", KeyCode::Key8),
+    ("9", KeyCode::Key9),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Space", KeyCode::Space),
+    ("LeftShift", KeyCode::LeftShift),
+    ("RightShift", KeyCode::RightShift),
+    ("LeftControl", KeyCode::LeftControl),
+    ("RightControl", KeyCode::RightControl),
+    ("LeftAlt", KeyCode::LeftAlt),
+    ("RightAlt", KeyCode::RightAlt),
+    ("Escape", KeyCode::Escape),
+];
+
+/// Action of the keyboard player that can be bound to a key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyAction {
+    LeftPaddleUp,
+    LeftPaddleDown,
+    RightPaddleUp,
+    RightPaddleDown,
+    Rematch,
+    Pause,
+}
+
+impl KeyAction {
+    pub const ALL: [KeyAction; 6] = [
+        KeyAction::LeftPaddleUp,
+        KeyAction::LeftPaddleDown,
+        KeyAction::RightPaddleUp,
+        KeyAction::RightPaddleDown,
+        KeyAction::Rematch,
+        KeyAction::Pause,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            KeyAction::LeftPaddleUp => "Left paddle up",
+            KeyAction::LeftPaddleDown => "Left paddle down",
+            KeyAction::RightPaddleUp => "Right paddle up",
+            KeyAction::RightPaddleDown => "Right paddle down",
+            KeyAction::Rematch => "Rematch",
+            KeyAction::Pause => "Pause",
+        }
+    }
+}
+
+/// Keys of the keyboard player, saved by name in the settings file.
+/// A name the game does not know falls back to the default key of its action.
+/// The menus, including the pause menu, are always navigated with the arrows, ENTER and ESC.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub left_paddle_up: String,
+    pub left_paddle_down: String,
+    pub right_paddle_up: String,
+    pub right_paddle_down: String,
+    pub rematch: String,
+    pub pause: String,
+}
+
+impl KeyBindings {
+    /// Return the name of the key bound to `action`
+    pub fn name(&self, action: KeyAction) -> &str {
+        match action {
+            KeyAction::LeftPaddleUp => &self.left_paddle_up,
+            KeyAction::LeftPaddleDown => &self.left_paddle_down,
+            KeyAction::RightPaddleUp => &self.right_paddle_up,
+            KeyAction::RightPaddleDown => &self.right_paddle_down,
+            KeyAction::Rematch => &self.rematch,
+            KeyAction::Pause => &self.pause,
+        }
+    }
+
+    /// Return the action `key` is bound to, if any
+    pub fn action_of(&self, key: KeyCode) -> Option<KeyAction> {
+        KeyAction::ALL
+            .into_iter()
+            .find(|action| self.key(*action) == key)
+    }
+
+    /// Return the key bound to `action`
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::key_bindings::{KeyAction, KeyBindings};
+    /// # use macroquad::prelude::KeyCode;
+    /// let mut bindings = KeyBindings::default();
+    /// assert_eq!(bindings.key(KeyAction::LeftPaddleUp), KeyCode::W);
+    /// bindings.left_paddle_up = "Hyper".to_owned();
+    /// assert_eq!(bindings.key(KeyAction::LeftPaddleUp), KeyCode::W);
+    /// ```
+    pub fn key(&self, action: KeyAction) -> KeyCode {
+        key_code(self.name(action))
+            .or_else(|| key_code(Self::default().name(action)))
+            .expect("Default keys have names.")
+    }
+
+    /// Bind `key` to `action`, return false if `key` cannot be bound or is bound to another action
+    pub fn bind(&mut self, action: KeyAction, key: KeyCode) -> bool {
+        let Some(name) = key_name(key) else {
+            return false;
+        };
+        if self.action_of(key).is_some_and(|bound| bound != action) {
+            return false;
+        }
+        let field = match action {
+            KeyAction::LeftPaddleUp => &mut self.left_paddle_up,
+            KeyAction::LeftPaddleDown => &mut self.left_paddle_down,
+            KeyAction::RightPaddleUp => &mut self.right_paddle_up,
+            KeyAction::RightPaddleDown => &mut self.right_paddle_down,
+            KeyAction::Rematch => &mut self.rematch,
+            KeyAction::Pause => &mut self.pause,
+        };
+        *field = name.to_owned();
+        true
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            left_paddle_up: "W".to_owned(),
+            left_paddle_down: "S".to_owned(),
+            right_paddle_up: "Up".to_owned(),
+            right_paddle_down: "Down".to_owned(),
+            rematch: "R".to_owned(),
+            pause: "Escape".to_owned(),
+        }
+    }
+}
+
+/// Return the key called `name` in the settings file, ignoring case
+fn key_code(name: &str) -> Option<KeyCode> {
+    KEY_NAMES
+        .iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        .map(|(_, key)| *key)
+}
+
+/// Return the name `key` is saved with in the settings file, if it can be bound
+fn key_name(key: KeyCode) -> Option<&'static str> {
+    KEY_NAMES
+        .iter()
+        .find(|(_, known)| *known == key)
+        .map(|(name, _)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bound_keys_are_saved_by_name() {
+        let mut bindings = KeyBindings::default();
+        assert!(bindings.bind(KeyAction::Rematch, KeyCode::Space));
+        assert!(!bindings.bind(KeyAction::Rematch, KeyCode::Enter));
+        assert_eq!(bindings.rematch, "Space");
+        assert_eq!(bindings.key(KeyAction::Rematch), KeyCode::Space);
+    }
+
+    #[test]
+    fn keys_are_bound_to_a_single_action() {
+        let mut bindings = KeyBindings::default();
+        assert!(!bindings.bind(KeyAction::Pause, KeyCode::Up));
+        assert!(!bindings.bind(KeyAction::Rematch, KeyCode::Escape));
+        assert_eq!(bindings.action_of(KeyCode::Escape), Some(KeyAction::Pause));
+        assert!(bindings.bind(KeyAction::Pause, KeyCode::P));
+        assert!(bindings.bind(KeyAction::Rematch, KeyCode::Escape));
+        assert!(bindings.bind(KeyAction::Rematch, KeyCode::Escape));
+    }
+
+    #[test]
+    fn names_ignore_case() {
+        let bindings = KeyBindings {
+            right_paddle_up: "leftshift".to_owned(),
+            ..KeyBindings::default()
+        };
+        assert_eq!(bindings.key(KeyAction::RightPaddleUp), KeyCode::LeftShift);
+    }
+}
@@ -0,0 +1,137 @@
+use std::path::PathBuf;
+
+use macroquad::logging::warn;
+
+use crate::{cpu::CpuLevel, room::Room};
+
+/// What the game does once started
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LaunchMode {
+    /// Show the main menu
+    #[default]
+    Menu,
+    /// Create a private room and wait for a friend to join it
+    Host,
+    /// Join a room as a player
+    Join(Room),
+    /// Play a match between two players sharing the keyboard
+    Local,
+    /// Play a match against a computer-controlled player
+    VsCpu(CpuLevel),
+    /// Play a local match through a GGRS synchronization test session
+    SyncTest,
+    /// Play back a replay file
+    Replay(PathBuf),
+}
+
+/// How the game was asked to start from the command line
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LaunchConfig {
+    pub mode: LaunchMode,
+    /// Matchbox server replacing the one of the settings
+    pub server: Option<String>,
+    /// If true, the match is simulated without opening a window
+    pub headless: bool,
+    /// Seed of the random source of a headless match, replacing [`crate::HEADLESS_DEFAULT_SEED`]
+    pub seed: Option<u64>,
+}
+
+impl LaunchConfig {
+    /// Parse the launch configuration from the command-line arguments.
+    /// Supports `--host`, `--join <room>`, `--local`, `--vs-cpu <level>`, `--synctest`, `--replay <file>`,
+    /// `--server <url>`, `--headless` and `--seed <n>`, other arguments are ignored. The last mode given wins.
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::{cpu::CpuLevel, launch::{LaunchConfig, LaunchMode}};
+    /// let args = ["--vs-cpu", "hard", "--headless", "--fps", "30"].map(String::from);
+    /// let launch = LaunchConfig::from_args(args);
+    /// assert_eq!(launch.mode, LaunchMode::VsCpu(CpuLevel::Hard));
+    /// assert!(launch.headless);
+    /// ```
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut launch = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mode = match arg.as_str() {
+                "--host" => Some(LaunchMode::Host),
+                "--local" => Some(LaunchMode::Local),
+                "--synctest" => Some(LaunchMode::SyncTest),
+                "--headless" => {
+                    launch.headless = true;
+                    continue;
+                }
+                "--server" => {
+                    match args.next() {
+                        Some(url) => launch.server = Some(url),
+                        None => warn!("Missing value for {arg}"),
+                    }
+                    continue;
+                }
+                "--seed" => {
+                    match args.next().and_then(|value| value.parse().ok()) {
+                        Some(seed) => launch.seed = Some(seed),
+                        None => warn!("Invalid value for {arg}, keeping the previous seed"),
+                    }
+                    continue;
+                }
+                "--join" | "--vs-cpu" | "--replay" => {
+                    let parsed = args.next().and_then(|value| match arg.as_str() {
+                        "--join" => Room::named(&value).ok().map(LaunchMode::Join),
+                        "--vs-cpu" => CpuLevel::parse(&value).map(LaunchMode::VsCpu),
+                        _ => Some(LaunchMode::Replay(PathBuf::from(value))),
+                    });
+                    if parsed.is_none() {
+                        warn!("Invalid value for {arg}, keeping the previous mode");
+                    }
+                    parsed
+                }
+                _ => continue,
+            };
+            if let Some(mode) = mode {
+                launch.mode = mode;
+            }
+        }
+        launch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_args_shows_the_menu() {
+        assert_eq!(LaunchConfig::from_args([]), LaunchConfig::default());
+        assert_eq!(LaunchConfig::default().mode, LaunchMode::Menu);
+    }
+
+    #[test]
+    fn last_mode_wins() {
+        let args = [
+            "--local",
+            "--server",
+            "ws://localhost:3536",
+            "--join",
+            "ab-12",
+        ];
+        let launch = LaunchConfig::from_args(args.map(String::from));
+        assert_eq!(launch.mode, LaunchMode::Join(Room::named("AB-12").unwrap()));
+        assert_eq!(launch.server.as_deref(), Some("ws://localhost:3536"));
+        assert!(!launch.headless);
+    }
+
+    #[test]
+    fn invalid_value_keeps_previous_mode() {
+        let args = ["--synctest", "--vs-cpu", "impossible", "--join", "a/b"];
+        let launch = LaunchConfig::from_args(args.map(String::from));
+        assert_eq!(launch.mode, LaunchMode::SyncTest);
+    }
+
+    #[test]
+    fn seed_from_args() {
+        let args = ["--seed", "42", "--headless", "--seed", "many"];
+        let launch = LaunchConfig::from_args(args.map(String::from));
+        assert_eq!(launch.seed, Some(42));
+        assert_eq!(LaunchConfig::default().seed, None);
+    }
+}
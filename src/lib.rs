@@ -1,33 +1,113 @@
 pub mod ball;
+pub mod camera;
+pub mod connecting;
+pub mod cpu;
+pub mod debug_overlay;
+pub mod effects;
 pub mod game;
 pub mod game_state;
 pub mod ggrs_config;
+pub mod handshake;
+pub mod headless;
 pub mod helpers;
 pub mod input;
+pub mod interpolation;
+pub mod key_bindings;
+pub mod launch;
 pub mod lobby;
+pub mod local_play;
+pub mod main_menu;
+pub mod match_rules;
+pub mod match_screen;
+pub mod online_match;
 pub mod paddle;
-pub mod screen_state;
+pub mod pause_menu;
+pub mod playfield;
+pub mod render;
+pub mod replay;
+pub mod replay_browser;
+pub mod replay_player;
+pub mod replay_screen;
+pub mod results;
+pub mod rng;
+pub mod room;
+pub mod screen;
+pub mod session_settings;
+pub mod settings_menu;
+pub mod snapshot;
+pub mod software_renderer;
+pub mod sound;
+pub mod spectating;
+pub mod sync_test;
+pub mod text_input;
+pub mod theme;
 pub mod traits;
 
+use instant::Duration;
+
 pub const BALL_SIZE: f32 = 10.0;
+pub const DEFAULT_FPS: usize = 60;
+pub const DEFAULT_INPUT_DELAY: usize = 2;
+pub const DEFAULT_MAX_PREDICTION_WINDOW: usize = 12;
+pub const DEFAULT_PLAYER_NAME: &str = "Player";
+pub const DEFAULT_REPLAY_DIR: &str = "replays";
+pub const DEFAULT_THEME: &str = "classic";
+pub const DEFAULT_VOLUME: u8 = 100;
 pub const EDGE_BOTTOM: i32 = 1;
 pub const EDGE_LEFT: i32 = 1;
 pub const EDGE_RIGHT: i32 = 502;
 pub const EDGE_TOP: i32 = 332;
+pub const EFFECTS_FLASH_SECONDS: f32 = 0.15;
+pub const EFFECTS_PARTICLES: usize = 12;
+pub const EFFECTS_PARTICLE_SECONDS: f32 = 0.4;
+pub const EFFECTS_SHAKE_SECONDS: f32 = 0.3;
+pub const EFFECTS_SHAKE_STRENGTH: f32 = 4.0;
+pub const EFFECTS_TRAIL_LENGTH: usize = 6;
 pub const FONT_SIZE: u16 = 32;
+pub const HANDSHAKE_HELLO_INTERVAL: Duration = Duration::from_millis(200);
+pub const HEADLESS_DEFAULT_SEED: u64 = 0;
+pub const HEADLESS_MAX_FRAMES: usize = 60 * 60 * 10;
 pub const INITIAL_VELOCITY: i32 = 3;
+pub const INPUT_FORFEIT: u8 = 0b1000_0000;
 pub const INPUT_LEFT_PADDLE_DOWN: u8 = 0b0010;
 pub const INPUT_LEFT_PADDLE_UP: u8 = 0b0001;
+pub const INPUT_PAUSE: u8 = 0b10_0000;
+pub const INPUT_REMATCH: u8 = 0b1_0000;
+pub const INPUT_RESUME: u8 = 0b100_0000;
 pub const INPUT_RIGHT_PADDLE_DOWN: u8 = 0b0100;
 pub const INPUT_RIGHT_PADDLE_UP: u8 = 0b1000;
+pub const INTERPOLATION_MAX_DISTANCE: i32 = 64;
+pub const MAX_AUTO_INPUT_DELAY: usize = 8;
+pub const MAX_PAUSES_PER_PLAYER: u8 = 3;
 pub const MATCHBOX_ADDR: &str = "wss://match.gschup.dev";
 pub const MIDDLE_POS: [i32; 2] = [256, 171];
+pub const NET_DASH_LENGTH: f32 = 10.0;
+pub const NET_WIDTH: f32 = 2.0;
+pub const NUM_PLAYERS: usize = 2;
 pub const PADDLE_BOTTOM_LIMIT: i32 = 1;
 pub const PADDLE_HEIGHT: f32 = 50.0;
+pub const PADDLE_SPEED: i32 = 2;
 pub const PADDLE_WIDTH: f32 = 10.0;
 pub const PADDLE_TOP_LIMIT: i32 = 291;
+pub const PING_PROBE_INTERVAL: Duration = Duration::from_millis(50);
+pub const PING_PROBE_SAMPLES: usize = 10;
+pub const PING_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+pub const PLAYER_NAME_MAX_LENGTH: usize = 12;
+pub const REPLAY_BROWSER_ROWS: usize = 8;
+pub const REPLAY_CHECKSUM_INTERVAL: usize = 60;
+pub const REPLAY_SEEK_SECONDS: usize = 5;
+pub const REPLAY_SNAPSHOT_INTERVAL: usize = 300;
+pub const REPLAY_SPEEDS: [f64; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+pub const ROOM_CODE_LENGTH: usize = 8;
+pub const ROOM_NAME_MAX_LENGTH: usize = 24;
 pub const SCORE_MAX_VALUE: i32 = 5;
 pub const SCORE_POS_X: f32 = 60.0;
 pub const SCORE_POS_Y: f32 = 35.0;
 pub const SCREEN_WIDTH: f32 = 512.0;
 pub const SCREEN_HEIGHT: f32 = 342.0;
+pub const SERVER_URL_MAX_LENGTH: usize = 120;
+pub const SETTINGS_PATH: &str = "settings.json";
+pub const SPECTATOR_CATCHUP_SPEED: usize = 2;
+pub const SPECTATOR_DELAY_FRAMES: usize = 6;
+pub const SPECTATOR_MAX_FRAMES_BEHIND: usize = 30;
+pub const SYNC_TEST_CHECK_DISTANCE: usize = 2;
@@ -1,127 +1,302 @@
+use std::{
+    iter,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use macroquad::prelude::*;
 
+use crate::{
+    connecting::Connecting,
+    handshake::{Handshake, PeerRole},
+    match_rules::MatchPreset,
+    render::{CommandList, ImageId},
+    room::{is_room_name_char, Room, RoomNameError},
+    screen::{Context, Screen, Transition},
+    text_input::{is_control_down, set_clipboard_text, TextInput},
+    DEFAULT_PLAYER_NAME, PLAYER_NAME_MAX_LENGTH, ROOM_NAME_MAX_LENGTH,
+};
+
+/// What the player chose to do in the lobby
+pub enum LobbyAction {
+    /// Join a matchbox room with the given role and display name, offering to host with the given rules
+    Join {
+        room: Room,
+        role: PeerRole,
+        preset: MatchPreset,
+        name: String,
+    },
+    /// Go back to the main menu
+    Back,
+}
+
+/// Return true if `c` can be part of a display name
+pub fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, ' ' | '-' | '_')
+}
+
 pub struct Lobby {
-    /// Lobby ID text field
-    text_field: String,
-    /// Good Game Rusty Pong Logo
-    logo: Texture2D,
+    /// Room name text field
+    room: TextInput,
+    /// Size of the Good Game Rusty Pong logo, the image itself is loaded by the render backends
+    logo_size: Vec2,
+    /// Rules of the match if the local player ends up hosting it
+    preset: MatchPreset,
+    /// Display name shown to the other peers
+    name: TextInput,
+    /// True if the typed characters go to the name instead of the room name
+    editing_name: bool,
+    /// Why the last typed characters or room name were refused
+    message: Option<String>,
 }
 
 impl Lobby {
-    pub fn new(logo: Texture2D) -> Self {
+    /// Return the lobby, with `name` as the display name until the player changes it
+    pub fn new(logo_size: Vec2, name: &str) -> Self {
+        let mut name_input = TextInput::new("", PLAYER_NAME_MAX_LENGTH, is_name_char);
+        name_input.set_text(name);
         Self {
-            text_field: "".to_owned(),
-            logo,
+            room: TextInput::new("", ROOM_NAME_MAX_LENGTH, is_room_name_char),
+            logo_size,
+            preset: MatchPreset::default(),
+            name: name_input,
+            editing_name: false,
+            message: None,
         }
     }
 
-    pub fn run(&mut self, font: Font) -> Option<String> {
-        if is_key_pressed(KeyCode::Key0) {
-            self.text_field.push('0');
-        }
-        if is_key_pressed(KeyCode::Key1) {
-            self.text_field.push('1');
+    /// Display name the player joins with, [`DEFAULT_PLAYER_NAME`] if left empty
+    pub fn name(&self) -> String {
+        match self.name.text().trim() {
+            "" => DEFAULT_PLAYER_NAME.to_owned(),
+            name => name.to_owned(),
         }
-        if is_key_pressed(KeyCode::Key2) {
-            self.text_field.push('2');
-        }
-        if is_key_pressed(KeyCode::Key3) {
-            self.text_field.push('3');
-        }
-        if is_key_pressed(KeyCode::Key4) {
-            self.text_field.push('4');
-        }
-        if is_key_pressed(KeyCode::Key5) {
-            self.text_field.push('5');
-        }
-        if is_key_pressed(KeyCode::Key6) {
-            self.text_field.push('6');
-        }
-        if is_key_pressed(KeyCode::Key7) {
-            self.text_field.push('7');
-        }
-        if is_key_pressed(KeyCode::Key8) {
-            self.text_field.push('8');
+    }
+
+    /// Handle the room name and name inputs and return the action chosen by the player, if any
+    fn handle_keys(&mut self) -> Option<LobbyAction> {
+        // characters are queued by macroquad, they are drained even when no field uses them
+        let typed: Vec<char> = iter::from_fn(get_char_pressed).collect();
+        if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Down) {
+            self.editing_name = !self.editing_name;
+        } else {
+            let field = if self.editing_name {
+                &mut self.name
+            } else {
+                &mut self.room
+            };
+            match field.handle_keys(&typed) {
+                Err(e) => self.message = Some(format!("Not typed: {e}")),
+                Ok(()) if !typed.is_empty() => self.message = None,
+                Ok(()) => {}
+            }
         }
-        if is_key_pressed(KeyCode::Key9) {
-            self.text_field.push('9');
+
+        if is_key_pressed(KeyCode::Tab) {
+            self.preset = self.preset.next();
         }
-        if is_key_pressed(KeyCode::Backspace) {
-            let mut chars = self.text_field.chars();
-            chars.next_back();
-            self.text_field = chars.as_str().to_owned();
+        if is_control_down() && is_key_pressed(KeyCode::G) {
+            let seed = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_nanos() as u64);
+            self.create_private_room(seed);
         }
-
-        if self.text_field.len() > 4 {
-            self.text_field = self.text_field[0..4].to_owned();
+        if is_control_down() && is_key_pressed(KeyCode::C) {
+            match Room::named(self.room.text()) {
+                Ok(Room::Named(code)) => {
+                    set_clipboard_text(&code);
+                    self.message = Some(format!("Copied {code}, send it to your friend"));
+                }
+                _ => self.message = Some("Type or create a room to copy it".to_owned()),
+            }
         }
 
-        self.render(font);
-
-        if is_key_pressed(KeyCode::Enter) && self.text_field.len() == 4 {
-            Some(format!("macro{}", self.text_field))
-        } else if is_key_pressed(KeyCode::Enter) && self.text_field.is_empty() {
-            Some("macro?next=2".to_owned())
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        if is_key_pressed(KeyCode::Enter) && shift {
+            self.join(PeerRole::Spectator)
+        } else if is_key_pressed(KeyCode::Enter) {
+            self.join(PeerRole::Player)
+        } else if is_key_pressed(KeyCode::Escape) {
+            Some(LobbyAction::Back)
         } else {
             None
         }
     }
 
-    fn render(&self, font: Font) {
-        clear_background(BLACK);
-        let dest_x = screen_width() / 2.0;
-        let dest_y = self.logo.height() * (dest_x / self.logo.width());
-        draw_texture_ex(
-            self.logo,
-            screen_width() / 2. - dest_x / 2.,
-            20.0,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(vec2(dest_x, dest_y)),
-                ..Default::default()
-            },
+    /// Fill the room name with a new private room code, drawn from `seed`
+    fn create_private_room(&mut self, seed: u64) {
+        if let Room::Named(code) = Room::private(seed) {
+            self.room.set_text(&code);
+        }
+        self.editing_name = false;
+        self.message = Some("Private room ready, CTRL+C to copy its code".to_owned());
+    }
+
+    /// Return the action joining the typed room with `role`, a random one for players leaving it empty,
+    /// or none after explaining why the room name is refused
+    fn join(&mut self, role: PeerRole) -> Option<LobbyAction> {
+        let room = match Room::named(self.room.text()) {
+            Ok(room) => room,
+            Err(RoomNameError::Empty) if role == PeerRole::Player => Room::Random,
+            Err(RoomNameError::Empty) => {
+                self.message = Some("Type the name of the room to watch".to_owned());
+                return None;
+            }
+            Err(e) => {
+                self.message = Some(e.to_string());
+                return None;
+            }
+        };
+        self.message = None;
+        Some(LobbyAction::Join {
+            room,
+            role,
+            preset: self.preset,
+            name: self.name(),
+        })
+    }
+
+    /// Record the lobby screen for a window `width` pixels wide
+    pub fn draw(&self, commands: &mut CommandList, width: f32) {
+        let dest_x = width / 2.0;
+        let dest_y = self.logo_size.y * (dest_x / self.logo_size.x);
+        commands.image(
+            ImageId::Logo,
+            Rect::new(width / 2. - dest_x / 2., 20.0, dest_x, dest_y),
         );
-        draw_text_ex(
-            "Play a friend using a 4 digits lobby ID",
+        commands.text(
+            "Play a friend by typing the same room name",
             20.0,
             dest_y + 70.0,
-            TextParams {
-                font_size: 30,
-                font,
-                ..Default::default()
-            },
+            30,
+            WHITE,
         );
-        draw_text_ex(
+        commands.text(
             "Or leave empty and play against a random person",
             20.0,
             dest_y + 110.0,
-            TextParams {
-                font_size: 30,
-                font,
-                ..Default::default()
-            },
+            30,
+            WHITE,
         );
-        draw_text_ex(
-            "Now, press ENTER to start!",
+        commands.text(
+            "Now, press ENTER to start, or SHIFT+ENTER to watch the room",
             20.0,
             dest_y + 150.0,
-            TextParams {
-                font_size: 30,
-                font,
-                ..Default::default()
-            },
+            30,
+            WHITE,
         );
 
-        let lobby_code_str = format!("GO! Lobby ID: {}", self.text_field);
-        draw_text_ex(
-            &lobby_code_str,
+        let room_str = format!("GO! Room: {}", self.room.display(!self.editing_name));
+        commands.text(&room_str, 20.0, dest_y + 190.0, 30, WHITE);
+
+        if let Some(message) = &self.message {
+            commands.text(message, 20.0, dest_y + 230.0, 30, RED);
+        }
+
+        commands.text(
+            "CTRL+G to create a private room, ESC to go back to the menu",
             20.0,
-            dest_y + 190.0,
-            TextParams {
-                font_size: 30,
-                font,
-                ..Default::default()
-            },
+            dest_y + 270.0,
+            30,
+            WHITE,
         );
+
+        let rules_str = format!("Rules if hosting: {} (TAB to change)", self.preset.name());
+        commands.text(&rules_str, 20.0, dest_y + 310.0, 30, WHITE);
+
+        let name_str = if self.editing_name {
+            format!("Your name: {} (UP/DOWN when done)", self.name.display(true))
+        } else {
+            format!("Your name: {} (UP/DOWN to edit)", self.name.text())
+        };
+        commands.text(&name_str, 20.0, dest_y + 350.0, 30, WHITE);
+    }
+}
+
+impl Screen for Lobby {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        match self.handle_keys() {
+            Some(LobbyAction::Join {
+                room,
+                role,
+                preset,
+                name,
+            }) => {
+                // the name is remembered for the next time the game is started
+                if name != ctx.settings.player_name {
+                    ctx.change_settings(|settings| settings.player_name = name.clone());
+                }
+                let handshake = Handshake::new(role, preset, name);
+                let connecting = Connecting::join(&room, &ctx.settings.server, handshake);
+                Transition::Push(Box::new(connecting))
+            }
+            Some(LobbyAction::Back) => Transition::Pop,
+            None => Transition::None,
+        }
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        let mut commands = CommandList::new();
+        self.draw(&mut commands, screen_width());
+        ctx.backend.execute(&commands);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_keep_allowed_characters_up_to_max_length() {
+        let mut lobby = Lobby::new(vec2(1.0, 1.0), DEFAULT_PLAYER_NAME);
+        lobby.name.set_text("");
+        assert!(lobby
+            .name
+            .insert(['A', 'l', '!', ' ', 'x', '\u{8}'])
+            .is_err());
+        assert_eq!(lobby.name.text(), "Al x");
+        assert!(lobby.name.insert(['y'; PLAYER_NAME_MAX_LENGTH]).is_err());
+        assert_eq!(lobby.name.text().chars().count(), PLAYER_NAME_MAX_LENGTH);
+    }
+
+    #[test]
+    fn blank_name_uses_default() {
+        let mut lobby = Lobby::new(vec2(1.0, 1.0), DEFAULT_PLAYER_NAME);
+        lobby.name.set_text("  ");
+        assert_eq!(lobby.name(), DEFAULT_PLAYER_NAME);
+        lobby.name.set_text(" Zoe ");
+        assert_eq!(lobby.name(), "Zoe");
+    }
+
+    #[test]
+    fn empty_room_is_random_for_players_only() {
+        let mut lobby = Lobby::new(vec2(1.0, 1.0), DEFAULT_PLAYER_NAME);
+        assert!(matches!(
+            lobby.join(PeerRole::Player),
+            Some(LobbyAction::Join {
+                room: Room::Random,
+                ..
+            })
+        ));
+        assert!(lobby.join(PeerRole::Spectator).is_none());
+        assert!(lobby.message.is_some());
+
+        lobby.room.set_text("friday-pong");
+        assert!(matches!(
+            lobby.join(PeerRole::Spectator),
+            Some(LobbyAction::Join { room: Room::Named(name), .. }) if name == "FRIDAY-PONG"
+        ));
+        assert!(lobby.message.is_none());
+    }
+
+    #[test]
+    fn private_room_code_is_joinable() {
+        let mut lobby = Lobby::new(vec2(1.0, 1.0), DEFAULT_PLAYER_NAME);
+        lobby.editing_name = true;
+        lobby.create_private_room(7);
+        assert!(!lobby.editing_name);
+        assert!(matches!(
+            lobby.join(PeerRole::Player),
+            Some(LobbyAction::Join { room, .. }) if room == Room::private(7)
+        ));
     }
 }
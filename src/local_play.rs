@@ -0,0 +1,256 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ggrs::InputStatus;
+use macroquad::prelude::*;
+
+use crate::{
+    cpu::CpuLevel,
+    game_state::GameState,
+    input::Input,
+    match_rules::{MatchPreset, MatchRules},
+    match_screen::{Match, MatchDriver, MatchScreen, MatchView},
+    render::CommandList,
+    screen::{Context, Screen, Transition},
+    session_settings::SessionSettings,
+    sound::GameSound,
+    INPUT_LEFT_PADDLE_DOWN, INPUT_LEFT_PADDLE_UP, INPUT_REMATCH, INPUT_RESUME,
+    INPUT_RIGHT_PADDLE_DOWN, INPUT_RIGHT_PADDLE_UP, NUM_PLAYERS,
+};
+
+/// Who moves the paddle of a player of a match played on this computer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Controller {
+    Keyboard,
+    Cpu(CpuLevel),
+}
+
+/// Return the input of every handle of a match between the `controllers`, `keys` being the input of the keyboard
+/// and `actions` the ones chosen in the pause menu by each handle.
+/// With two keyboard players, the first moves its paddle with `W`/`S` and the second with the arrows, on whichever
+/// side they play, while a single one moves its paddle with either. Every player agrees with the rematch and resume
+/// votes of the others.
+/// # Examples
+/// ```
+/// # use good_game_rusty_pong::{*, game_state::GameState, local_play::*};
+/// let actions = [0, INPUT_FORFEIT];
+/// let controllers = [Controller::Keyboard; NUM_PLAYERS];
+/// let inputs = local_inputs(&GameState::new(), INPUT_RIGHT_PADDLE_UP, actions, &controllers);
+/// assert_eq!(inputs, [0, INPUT_RIGHT_PADDLE_UP | INPUT_FORFEIT]);
+/// ```
+pub fn local_inputs(
+    state: &GameState,
+    keys: u8,
+    actions: [u8; NUM_PLAYERS],
+    controllers: &[Controller; NUM_PLAYERS],
+) -> [u8; NUM_PLAYERS] {
+    let votes =
+        actions.iter().fold(keys, |all, action| all | action) & (INPUT_REMATCH | INPUT_RESUME);
+    let paddle_keys = INPUT_LEFT_PADDLE_UP
+        | INPUT_LEFT_PADDLE_DOWN
+        | INPUT_RIGHT_PADDLE_UP
+        | INPUT_RIGHT_PADDLE_DOWN;
+    let keyboards = controllers
+        .iter()
+        .filter(|controller| **controller == Controller::Keyboard)
+        .count();
+    let mut inputs = actions.map(|action| action | votes);
+    for (handle, controller) in controllers.iter().enumerate() {
+        inputs[handle] |= match controller {
+            Controller::Cpu(level) => level.input(state, state.side_of(handle)),
+            Controller::Keyboard if keyboards > 1 && handle == 0 => {
+                keys & (INPUT_LEFT_PADDLE_UP | INPUT_LEFT_PADDLE_DOWN)
+            }
+            Controller::Keyboard if keyboards > 1 => {
+                keys & (INPUT_RIGHT_PADDLE_UP | INPUT_RIGHT_PADDLE_DOWN)
+            }
+            Controller::Keyboard => keys & paddle_keys,
+        };
+    }
+    inputs
+}
+
+/// Return the handles of the players using the keyboard
+pub fn keyboard_handles(controllers: &[Controller; NUM_PLAYERS]) -> Vec<usize> {
+    (0..NUM_PLAYERS)
+        .filter(|handle| controllers[*handle] == Controller::Keyboard)
+        .collect()
+}
+
+/// Plays a match on this computer, without any network, between players sharing the keyboard or computer-controlled
+pub struct LocalDriver {
+    controllers: [Controller; NUM_PLAYERS],
+}
+
+impl LocalDriver {
+    pub fn new(controllers: [Controller; NUM_PLAYERS]) -> Self {
+        Self { controllers }
+    }
+
+    /// Start a match between the `controllers` playing by `rules`
+    pub fn start(
+        controllers: [Controller; NUM_PLAYERS],
+        rules: MatchRules,
+        settings: &SessionSettings,
+    ) -> Match {
+        let view = MatchView::new(new_local_game(rules), Vec::new(), settings.effects);
+        Match::new(Box::new(Self::new(controllers)), view)
+    }
+}
+
+/// Return the game state of a new local match playing by `rules`, seeded from the clock
+pub fn new_local_game(rules: MatchRules) -> GameState {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64);
+    GameState::for_match(rules, seed)
+}
+
+impl MatchDriver for LocalDriver {
+    fn advance(
+        &mut self,
+        state: &mut GameState,
+        keys: u8,
+        actions: [u8; NUM_PLAYERS],
+        sounds: &[GameSound],
+    ) -> bool {
+        let inputs = local_inputs(state, keys, actions, &self.controllers)
+            .into_iter()
+            .map(|inp| (Input { inp }, InputStatus::Confirmed))
+            .collect();
+        state.advance(inputs, sounds);
+        true
+    }
+
+    fn local_handle(&self) -> Option<usize> {
+        // a match between computer-controlled players is only watched
+        self.controllers
+            .iter()
+            .position(|controller| *controller == Controller::Keyboard)
+    }
+
+    fn local_handles(&self) -> Vec<usize> {
+        keyboard_handles(&self.controllers)
+    }
+}
+
+/// Screen setting up a match played on this computer
+pub struct LocalPlay {
+    /// Rules of the next match
+    preset: MatchPreset,
+}
+
+impl LocalPlay {
+    pub fn new() -> Self {
+        Self {
+            preset: MatchPreset::default(),
+        }
+    }
+
+    /// Record the setup screen in window coordinates
+    pub fn draw(&self, commands: &mut CommandList) {
+        let rules_str = format!("Rules: {} (TAB to change)", self.preset.name());
+        let lines = [
+            "Local match",
+            "First player, starting on the left: W and S",
+            "Second player, starting on the right: UP and DOWN",
+            rules_str.as_str(),
+            "Press ENTER to start, or ESC to go back",
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            commands.text(line, 20.0, 60.0 + 40.0 * i as f32, 30, WHITE);
+        }
+    }
+}
+
+impl Default for LocalPlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Screen for LocalPlay {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Tab) {
+            self.preset = self.preset.next();
+        }
+
+        if is_key_pressed(KeyCode::Enter) {
+            let controllers = [Controller::Keyboard; NUM_PLAYERS];
+            let game = LocalDriver::start(controllers, self.preset.rules(), &ctx.settings);
+            Transition::Push(Box::new(MatchScreen::new(game)))
+        } else if is_key_pressed(KeyCode::Escape) {
+            Transition::Pop
+        } else {
+            Transition::None
+        }
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        let mut commands = CommandList::new();
+        self.draw(&mut commands);
+        ctx.backend.execute(&commands);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game_state::Side, INPUT_FORFEIT};
+
+    #[test]
+    fn single_keyboard_player_moves_its_own_paddle() {
+        let mut state = GameState::new();
+        state.sides_switched = true;
+        state.ball.vel_x = 3;
+        let controllers = [Controller::Keyboard, Controller::Cpu(CpuLevel::Medium)];
+        let keys = INPUT_LEFT_PADDLE_DOWN | INPUT_REMATCH;
+        let inputs = local_inputs(&state, keys, [0; NUM_PLAYERS], &controllers);
+        assert_eq!(inputs[0], keys);
+        let cpu_input = CpuLevel::Medium.input(&state, Side::Left);
+        assert_eq!(inputs[1], cpu_input | INPUT_REMATCH);
+
+        state.advance(
+            inputs
+                .map(|inp| (Input { inp }, InputStatus::Confirmed))
+                .to_vec(),
+            &[],
+        );
+        assert!(state.right_paddle.vel > 0);
+    }
+
+    #[test]
+    fn keyboard_players_keep_their_keys_after_switching_sides() {
+        let mut state = GameState::new();
+        state.sides_switched = true;
+        let keys = INPUT_LEFT_PADDLE_UP | INPUT_RIGHT_PADDLE_DOWN;
+        let controllers = [Controller::Keyboard; NUM_PLAYERS];
+        let inputs = local_inputs(&state, keys, [0; NUM_PLAYERS], &controllers);
+        assert_eq!(inputs, [INPUT_LEFT_PADDLE_UP, INPUT_RIGHT_PADDLE_DOWN]);
+
+        state.advance(
+            inputs
+                .map(|inp| (Input { inp }, InputStatus::Confirmed))
+                .to_vec(),
+            &[],
+        );
+        assert!(state.right_paddle.vel < 0);
+        assert!(state.left_paddle.vel > 0);
+    }
+
+    #[test]
+    fn keyboard_players_forfeit_for_themselves() {
+        let mut state = GameState::new();
+        state.paused_by = Some(0);
+        let controllers = [Controller::Keyboard; NUM_PLAYERS];
+        let inputs = local_inputs(&state, 0, [0, INPUT_FORFEIT], &controllers);
+        assert_eq!(inputs, [0, INPUT_FORFEIT]);
+
+        state.advance(
+            inputs
+                .map(|inp| (Input { inp }, InputStatus::Confirmed))
+                .to_vec(),
+            &[],
+        );
+        assert_eq!(state.winner, Some(state.side_of(0)));
+    }
+}
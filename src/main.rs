@@ -1,9 +1,39 @@
-use good_game_rusty_pong::game::Game;
+use std::{env, process::ExitCode};
 
-use macroquad::texture::Texture2D;
+use good_game_rusty_pong::{
+    game::Game, headless, launch::LaunchConfig, session_settings::SessionSettings, SCREEN_HEIGHT,
+    SCREEN_WIDTH, SETTINGS_PATH,
+};
 
-#[macroquad::main("GoodGameRustyPong")]
-async fn main() {
+use macroquad::{texture::Texture2D, window::Conf, Window};
+
+/// Open a resizable window twice as large as the playfield, which is scaled to whatever size the window gets,
+/// or a fullscreen one if the settings say so
+fn window_conf(settings: &SessionSettings) -> Conf {
+    Conf {
+        window_title: "GoodGameRustyPong".to_owned(),
+        window_width: SCREEN_WIDTH as i32 * 2,
+        window_height: SCREEN_HEIGHT as i32 * 2,
+        window_resizable: true,
+        fullscreen: settings.fullscreen,
+        ..Default::default()
+    }
+}
+
+async fn run(settings: SessionSettings, launch: LaunchConfig) {
     let logo = Texture2D::from_file_with_format(include_bytes!("../assets/logo.png"), None);
-    Game::new(logo).run().await;
+    let mut game = Game::new(logo, settings, launch);
+    game.run().await;
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let settings = SessionSettings::load(SETTINGS_PATH).with_args(args.clone());
+    let launch = LaunchConfig::from_args(args);
+
+    if launch.headless {
+        return headless::run(&launch, &settings);
+    }
+    Window::from_config(window_conf(&settings), run(settings, launch));
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,108 @@
+use macroquad::prelude::*;
+
+use crate::{
+    lobby::Lobby,
+    local_play::LocalPlay,
+    render::{CommandList, ImageId},
+    replay_browser::ReplayBrowser,
+    screen::{Context, Screen, Transition},
+    settings_menu::SettingsMenu,
+    DEFAULT_REPLAY_DIR,
+};
+
+/// Entry of the main menu, each opening its own screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuEntry {
+    PlayOnline,
+    PlayLocally,
+    Replays,
+    Settings,
+}
+
+/// First screen of the game
+pub struct MainMenu {
+    /// Size of the Good Game Rusty Pong logo, the image itself is loaded by the render backends
+    logo_size: Vec2,
+    /// Index of the highlighted entry in [`MainMenu::ENTRIES`]
+    selected: usize,
+}
+
+impl MainMenu {
+    const ENTRIES: [MenuEntry; 4] = [
+        MenuEntry::PlayOnline,
+        MenuEntry::PlayLocally,
+        MenuEntry::Replays,
+        MenuEntry::Settings,
+    ];
+
+    pub fn new(logo_size: Vec2) -> Self {
+        Self {
+            logo_size,
+            selected: 0,
+        }
+    }
+
+    /// Record the main menu for a window `width` pixels wide
+    pub fn draw(&self, commands: &mut CommandList, width: f32) {
+        let dest_x = width / 2.0;
+        let dest_y = self.logo_size.y * (dest_x / self.logo_size.x);
+        commands.image(
+            ImageId::Logo,
+            Rect::new(width / 2. - dest_x / 2., 20.0, dest_x, dest_y),
+        );
+
+        for (i, entry) in Self::ENTRIES.iter().enumerate() {
+            let label = match entry {
+                MenuEntry::PlayOnline => "Play online",
+                MenuEntry::PlayLocally => "Play on this computer",
+                MenuEntry::Replays => "Watch replays",
+                MenuEntry::Settings => "Settings",
+            };
+            let color = if i == self.selected { WHITE } else { GRAY };
+            commands.centered_text(
+                label,
+                width / 2.0,
+                dest_y + 90.0 + 50.0 * i as f32,
+                36,
+                color,
+            );
+        }
+    }
+}
+
+impl Screen for MainMenu {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1).min(Self::ENTRIES.len() - 1);
+        }
+
+        if !is_key_pressed(KeyCode::Enter) {
+            return Transition::None;
+        }
+        let screen: Box<dyn Screen> = match Self::ENTRIES[self.selected] {
+            MenuEntry::PlayOnline => {
+                Box::new(Lobby::new(self.logo_size, &ctx.settings.player_name))
+            }
+            MenuEntry::PlayLocally => Box::new(LocalPlay::new()),
+            MenuEntry::Replays => {
+                let replay_dir = ctx
+                    .settings
+                    .replay_dir
+                    .as_deref()
+                    .unwrap_or(DEFAULT_REPLAY_DIR);
+                Box::new(ReplayBrowser::new(replay_dir))
+            }
+            MenuEntry::Settings => Box::new(SettingsMenu::new()),
+        };
+        Transition::Push(screen)
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        let mut commands = CommandList::new();
+        self.draw(&mut commands, screen_width());
+        ctx.backend.execute(&commands);
+    }
+}
@@ -0,0 +1,197 @@
+use std::hash::Hasher;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    helpers::StateHasher, traits::StateHash, INITIAL_VELOCITY, PADDLE_HEIGHT, PADDLE_SPEED,
+    SCORE_MAX_VALUE,
+};
+
+/// Gameplay values of a match, agreed on by every peer before it starts and stored in the game state
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchRules {
+    /// Score a paddle needs to win a set
+    pub score_limit: i32,
+    /// Speed of the ball in both x direction and y direction
+    pub ball_speed: i32,
+    /// Speed of the paddles while a direction is held
+    pub paddle_speed: i32,
+    /// Height of the paddles
+    pub paddle_height: i32,
+    /// If true, a set is only won with a lead of two points, like in table tennis
+    pub win_by_two: bool,
+    /// Score that wins a set even without a lead of two points, if any
+    pub score_cap: Option<i32>,
+    /// Amount of sets the match is played over, the first paddle to win the majority of them wins the match
+    pub best_of: i32,
+    /// If true, the paddles switch sides with their sets between sets
+    pub switch_sides: bool,
+}
+
+impl MatchRules {
+    /// Return the rules of the original game
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::{SCORE_MAX_VALUE, INITIAL_VELOCITY, match_rules::MatchRules};
+    /// let rules = MatchRules::classic();
+    /// assert_eq!(rules.score_limit, SCORE_MAX_VALUE);
+    /// assert_eq!(rules.ball_speed, INITIAL_VELOCITY);
+    /// ```
+    pub fn classic() -> Self {
+        Self {
+            score_limit: SCORE_MAX_VALUE,
+            ball_speed: INITIAL_VELOCITY,
+            paddle_speed: PADDLE_SPEED,
+            paddle_height: PADDLE_HEIGHT as i32,
+            win_by_two: false,
+            score_cap: None,
+            best_of: 1,
+            switch_sides: false,
+        }
+    }
+
+    /// Return true if a paddle with `score` won the set against a paddle with `opponent_score`
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::match_rules::MatchRules;
+    /// let rules = MatchRules {
+    ///     score_limit: 11,
+    ///     win_by_two: true,
+    ///     score_cap: Some(15),
+    ///     ..MatchRules::classic()
+    /// };
+    /// assert!(!rules.set_won(10, 9));
+    /// assert!(rules.set_won(11, 9));
+    /// assert!(!rules.set_won(11, 10));
+    /// assert!(rules.set_won(13, 11));
+    /// assert!(rules.set_won(15, 14));
+    /// ```
+    pub fn set_won(&self, score: i32, opponent_score: i32) -> bool {
+        if score < self.score_limit {
+            return false;
+        }
+        !self.win_by_two
+            || score - opponent_score >= 2
+            || self.score_cap.is_some_and(|cap| score >= cap)
+    }
+
+    /// Amount of sets a paddle needs to win the match
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::match_rules::MatchRules;
+    /// let rules = MatchRules {
+    ///     best_of: 5,
+    ///     ..MatchRules::classic()
+    /// };
+    /// assert_eq!(rules.sets_to_win(), 3);
+    /// assert_eq!(MatchRules::classic().sets_to_win(), 1);
+    /// ```
+    pub fn sets_to_win(&self) -> i32 {
+        self.best_of / 2 + 1
+    }
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+impl StateHash for MatchRules {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_i32(self.score_limit);
+        hasher.write_i32(self.ball_speed);
+        hasher.write_i32(self.paddle_speed);
+        hasher.write_i32(self.paddle_height);
+        hasher.write_u8(self.win_by_two as u8);
+        // an option is a 0, or a 1 followed by its value
+        match self.score_cap {
+            None => hasher.write_u8(0),
+            Some(cap) => {
+                hasher.write_u8(1);
+                hasher.write_i32(cap);
+            }
+        }
+        hasher.write_i32(self.best_of);
+        hasher.write_u8(self.switch_sides as u8);
+    }
+}
+
+/// Set of rules the host can pick in the lobby
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatchPreset {
+    #[default]
+    Classic,
+    /// Faster ball and paddles, with shorter paddles
+    Fast,
+    /// Classic speeds, played to a higher score
+    Long,
+    /// Best of five sets to 11, won by two points, switching sides between sets
+    TableTennis,
+}
+
+impl MatchPreset {
+    pub const ALL: [MatchPreset; 4] = [
+        MatchPreset::Classic,
+        MatchPreset::Fast,
+        MatchPreset::Long,
+        MatchPreset::TableTennis,
+    ];
+
+    pub fn rules(self) -> MatchRules {
+        let classic = MatchRules::classic();
+        match self {
+            MatchPreset::Classic => classic,
+            MatchPreset::Fast => MatchRules {
+                ball_speed: 5,
+                paddle_speed: 3,
+                paddle_height: 40,
+                ..classic
+            },
+            MatchPreset::Long => MatchRules {
+                score_limit: 11,
+                ..classic
+            },
+            MatchPreset::TableTennis => MatchRules {
+                score_limit: 11,
+                win_by_two: true,
+                best_of: 5,
+                switch_sides: true,
+                ..classic
+            },
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MatchPreset::Classic => "Classic",
+            MatchPreset::Fast => "Fast",
+            MatchPreset::Long => "Long match",
+            MatchPreset::TableTennis => "Table tennis",
+        }
+    }
+
+    /// Return the preset after this one, wrapping around to the first
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::match_rules::MatchPreset;
+    /// assert_eq!(MatchPreset::Classic.next(), MatchPreset::Fast);
+    /// assert_eq!(MatchPreset::TableTennis.next(), MatchPreset::Classic);
+    /// ```
+    pub fn next(self) -> Self {
+        let index = self.index() as usize;
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Index of the preset in [`Self::ALL`], as sent to the other peers
+    pub fn index(self) -> u32 {
+        Self::ALL
+            .iter()
+            .position(|preset| *preset == self)
+            .expect("Every preset is listed.") as u32
+    }
+
+    pub fn from_index(index: u32) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+}
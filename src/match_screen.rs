@@ -0,0 +1,308 @@
+use std::fs;
+
+use instant::{Duration, Instant};
+use macroquad::prelude::*;
+
+use crate::{
+    effects::Effects,
+    game_state::GameState,
+    interpolation::RenderPositions,
+    key_bindings::KeyAction,
+    pause_menu::{PauseAction, PauseMenu},
+    playfield::{draw_court, draw_names, draw_playfield},
+    render::{CommandList, MacroquadBackend},
+    results::ResultsScreen,
+    screen::{Context, Screen, Transition},
+    settings_menu::SettingsMenu,
+    snapshot,
+    sound::GameSound,
+    theme::Theme,
+    INPUT_FORFEIT, INPUT_PAUSE, INPUT_RESUME, NUM_PLAYERS,
+};
+
+/// Where the frames of a match come from: the network, the local keyboard...
+pub trait MatchDriver {
+    /// Do the work needed once per displayed frame before simulating, like polling the network
+    fn poll(&mut self) {}
+
+    /// Seconds between two simulated frames, for a session running at `fps`
+    fn frame_seconds(&self, fps: usize) -> f64 {
+        1. / fps as f64
+    }
+
+    /// Simulate the next frame into `state`, `keys` being the input of the keyboard of this computer, and `actions`
+    /// the input bits of the actions chosen in the pause menu by each local handle. Return false if no frame could be
+    /// simulated, the actions are then sent again with the next one.
+    fn advance(
+        &mut self,
+        state: &mut GameState,
+        keys: u8,
+        actions: [u8; NUM_PLAYERS],
+        sounds: &[GameSound],
+    ) -> bool;
+
+    /// Do the work needed once per displayed frame after simulating, like saving the replay of a finished match
+    fn after_frames(&mut self) {}
+
+    /// Handle of the local player, or none when watching the match
+    fn local_handle(&self) -> Option<usize>;
+
+    /// Handles of every player of this computer, more than one when players share the keyboard
+    fn local_handles(&self) -> Vec<usize> {
+        self.local_handle().into_iter().collect()
+    }
+
+    /// Draw over the match, in window coordinates
+    fn draw_overlay(&mut self, _backend: &MacroquadBackend) {}
+}
+
+/// What is displayed of a match: its last two simulated frames, their effects and the names of the players
+pub struct MatchView {
+    pub game_state: GameState,
+    /// Game state before the last simulated frame, rendering interpolates from it to [`Self::game_state`]
+    pub previous_state: GameState,
+    pub effects: Effects,
+    /// Display names of the players ordered by their handle, empty if unknown
+    pub player_names: Vec<String>,
+}
+
+impl MatchView {
+    pub fn new(game_state: GameState, player_names: Vec<String>, effects: bool) -> Self {
+        Self {
+            game_state,
+            previous_state: game_state,
+            effects: Effects::new(effects),
+            player_names,
+        }
+    }
+
+    /// Record the playfield in playfield coordinates, with the moving objects `alpha` of a frame past the previous
+    /// simulated frame, and the pause screen while the match is paused
+    pub fn draw(
+        &self,
+        commands: &mut CommandList,
+        theme: &Theme,
+        local_handle: Option<usize>,
+        alpha: f32,
+        pause_menu: &PauseMenu,
+    ) {
+        let positions = RenderPositions::interpolate(&self.previous_state, &self.game_state, alpha);
+        draw_court(theme, commands);
+        self.effects.draw_under(commands, theme);
+        draw_playfield(&self.game_state, positions, theme, commands);
+        draw_names(
+            &self.game_state,
+            &self.player_names,
+            local_handle,
+            theme,
+            commands,
+        );
+        self.effects
+            .draw_over(commands, theme, &self.game_state, positions);
+        commands.translate(self.effects.shake_offset());
+        pause_menu.draw(
+            commands,
+            theme,
+            &self.game_state,
+            &self.player_names,
+            local_handle,
+            self.effects.is_enabled(),
+        );
+    }
+}
+
+/// A match being played or watched, simulated at the pace of its driver
+pub struct Match {
+    driver: Box<dyn MatchDriver>,
+    pub view: MatchView,
+    /// Input bits of the actions chosen in the pause menu by each local handle, sent with the next frame
+    pending_actions: [u8; NUM_PLAYERS],
+    last_update: Instant,
+    accumulator: Duration,
+    /// How far the display is between the previous and the current frame
+    alpha: f32,
+}
+
+impl Match {
+    pub fn new(driver: Box<dyn MatchDriver>, view: MatchView) -> Self {
+        Self {
+            driver,
+            view,
+            pending_actions: [0; NUM_PLAYERS],
+            last_update: Instant::now(),
+            accumulator: Duration::ZERO,
+            alpha: 0.0,
+        }
+    }
+
+    pub fn game_state(&self) -> &GameState {
+        &self.view.game_state
+    }
+
+    pub fn local_handle(&self) -> Option<usize> {
+        self.driver.local_handle()
+    }
+
+    pub fn local_handles(&self) -> Vec<usize> {
+        self.driver.local_handles()
+    }
+
+    /// Add `bits` to the next input of the local player with `handle`
+    pub fn send(&mut self, handle: usize, bits: u8) {
+        self.pending_actions[handle] |= bits;
+    }
+
+    /// Simulate the frames due since the previous update, and age the effects
+    pub fn update(&mut self, ctx: &Context) {
+        self.driver.poll();
+
+        let frame_seconds = self.driver.frame_seconds(ctx.settings.fps);
+        let delta = Instant::now().duration_since(self.last_update);
+        self.accumulator = self.accumulator.saturating_add(delta);
+        self.last_update = Instant::now();
+
+        while self.accumulator.as_secs_f64() > frame_seconds {
+            // decrease accumulator
+            self.accumulator = self
+                .accumulator
+                .saturating_sub(Duration::from_secs_f64(frame_seconds));
+
+            let before = self.view.game_state;
+            let keys = before.local_input(&ctx.settings.key_bindings).inp;
+            if self.driver.advance(
+                &mut self.view.game_state,
+                keys,
+                self.pending_actions,
+                &ctx.sounds,
+            ) {
+                self.pending_actions = [0; NUM_PLAYERS];
+                self.view.previous_state = before;
+                self.view
+                    .effects
+                    .observe(&self.view.previous_state, &self.view.game_state);
+            }
+        }
+
+        self.driver.after_frames();
+        self.view.effects.update(delta.as_secs_f32());
+        self.alpha = (self.accumulator.as_secs_f64() / frame_seconds) as f32;
+    }
+
+    /// Draw the playfield scaled to the window, then the overlay of the driver
+    pub fn render(&mut self, ctx: &Context, pause_menu: &PauseMenu) {
+        let mut commands = CommandList::new();
+        self.view.draw(
+            &mut commands,
+            &ctx.theme,
+            self.local_handle(),
+            self.alpha,
+            pause_menu,
+        );
+        ctx.backend.execute_on_playfield(&commands);
+        self.driver.draw_overlay(&ctx.backend);
+    }
+
+    /// Draw only the overlay of the driver, for screens drawing the match themselves
+    pub fn render_overlay(&mut self, ctx: &Context) {
+        self.driver.draw_overlay(&ctx.backend);
+    }
+}
+
+/// Screen of a match in progress, with the pause menu while it is paused
+pub struct MatchScreen {
+    /// The match, only taken when it moves to the results screen
+    game: Option<Match>,
+    pause_menu: PauseMenu,
+}
+
+impl MatchScreen {
+    pub fn new(game: Match) -> Self {
+        let pause_menu = PauseMenu::new(&game.local_handles());
+        Self {
+            game: Some(game),
+            pause_menu,
+        }
+    }
+}
+
+impl Screen for MatchScreen {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        let game = self
+            .game
+            .as_mut()
+            .expect("The match is only taken when leaving the screen.");
+        game.update(ctx);
+
+        if is_key_pressed(KeyCode::F4) {
+            export_game_state(game.game_state());
+        }
+
+        if game.game_state().winner.is_some() {
+            let game = self.game.take().expect("The match was just updated.");
+            return Transition::Replace(Box::new(ResultsScreen::new(game)));
+        }
+
+        // pausing goes through the inputs, so both players freeze on the same frame
+        match game.local_handle() {
+            // spectators can leave at any time
+            None if is_key_pressed(KeyCode::Escape) => return Transition::Pop,
+            None => {}
+            Some(_) if game.game_state().paused_by.is_some() => {
+                match self.pause_menu.handle_keys() {
+                    Some(PauseAction::Resume) => {
+                        for handle in game.local_handles() {
+                            game.send(handle, INPUT_RESUME);
+                        }
+                    }
+                    Some(PauseAction::ToggleEffects) => {
+                        let enabled = !game.view.effects.is_enabled();
+                        game.view.effects.set_enabled(enabled);
+                    }
+                    Some(PauseAction::Settings) => {
+                        return Transition::Push(Box::new(SettingsMenu::new()))
+                    }
+                    Some(PauseAction::Forfeit(handle)) => game.send(handle, INPUT_FORFEIT),
+                    None => {}
+                }
+            }
+            Some(handle) if is_key_pressed(ctx.settings.key_bindings.key(KeyAction::Pause)) => {
+                // players sharing the keyboard pause with the pauses of whoever has some left
+                let state = game.game_state();
+                let handle = game
+                    .local_handles()
+                    .into_iter()
+                    .find(|handle| state.pauses_left[*handle] > 0)
+                    .unwrap_or(handle);
+                game.send(handle, INPUT_PAUSE);
+                self.pause_menu.reset();
+            }
+            Some(_) => {}
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        if let Some(game) = &mut self.game {
+            game.render(ctx, &self.pause_menu);
+        }
+    }
+
+    fn update_below(&mut self, ctx: &mut Context) {
+        // the other player is still connected, and may forfeit meanwhile
+        if let Some(game) = &mut self.game {
+            game.update(ctx);
+        }
+    }
+}
+
+/// Write `state` as JSON to the working directory, for debugging
+fn export_game_state(state: &GameState) {
+    let path = format!("gamestate-{}.json", state.frame());
+    match snapshot::to_json(state) {
+        Ok(json) => match fs::write(&path, json) {
+            Ok(()) => info!("Game state exported to {path}"),
+            Err(e) => error!("Could not write {path}: {e}"),
+        },
+        Err(e) => error!("Could not export the game state: {e}"),
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_executor::LocalExecutor;
+use ggrs::{GGRSError, GGRSRequest, P2PSession, PlayerType, SessionBuilder, SessionState};
+use macroquad::prelude::*;
+use matchbox_socket::WebRtcSocket;
+
+use crate::{
+    debug_overlay::DebugOverlay,
+    game_state::GameState,
+    ggrs_config::GGRSConfig,
+    handshake::{Handshake, HandshakeAwareSocket, PeerRole},
+    input::Input,
+    match_rules::MatchRules,
+    match_screen::{Match, MatchDriver, MatchView},
+    render::MacroquadBackend,
+    replay::{Replay, ReplayError, ReplayHeader, ReplayRecorder, REPLAY_EXTENSION},
+    session_settings::SessionSettings,
+    sound::GameSound,
+    NUM_PLAYERS,
+};
+
+/// Plays a match against a remote player through a GGRS peer to peer session, recording its replay if enabled
+pub struct OnlineDriver {
+    session: P2PSession<GGRSConfig>,
+    /// Runs the message loop of the socket
+    executor: LocalExecutor<'static>,
+    recorder: Option<ReplayRecorder>,
+    replay_path: Option<PathBuf>,
+    debug_overlay: DebugOverlay,
+}
+
+impl OnlineDriver {
+    /// Start the match agreed on during the `handshake`, over `socket` whose message loop runs on `executor`
+    pub fn start(
+        socket: WebRtcSocket,
+        handshake: &Handshake,
+        executor: LocalExecutor<'static>,
+        settings: &SessionSettings,
+        input_delay: usize,
+    ) -> Match {
+        let local_id = socket.id().clone();
+        let seed = handshake.match_seed(&local_id);
+        let rules = handshake
+            .match_rules(&local_id)
+            .expect("Should only start a session once the rules are known.");
+
+        // create a new game
+        info!("Starting new game with {rules:?}...");
+        let game_state = GameState::for_match(rules, seed);
+
+        // create a new ggrs session
+        let mut sess_build = SessionBuilder::<GGRSConfig>::new()
+            .with_num_players(NUM_PLAYERS)
+            .with_max_prediction_window(settings.max_prediction_window)
+            .with_fps(settings.fps)
+            .expect("Invalid FPS")
+            .with_input_delay(input_delay);
+
+        // add players
+        let players = handshake.players(&local_id);
+        for (i, player_type) in players.into_iter().enumerate() {
+            sess_build = sess_build
+                .add_player(player_type, i)
+                .expect("Invalid player added.");
+        }
+
+        // the player with the first handle sends the confirmed inputs to the spectators,
+        // and tells the ones joining after the start that they are too late
+        let socket = if handshake.host_id(&local_id) == Some(local_id.clone()) {
+            let spectators = handshake.remote_ids(PeerRole::Spectator);
+            for (i, spectator) in spectators.iter().enumerate() {
+                info!("Adding spectator {spectator}");
+                sess_build = sess_build
+                    .add_player(PlayerType::Spectator(spectator.clone()), NUM_PLAYERS + i)
+                    .expect("Invalid spectator added.");
+            }
+            HandshakeAwareSocket::hosting(socket, spectators)
+        } else {
+            HandshakeAwareSocket::new(socket)
+        };
+
+        // start the GGRS session
+        let session = sess_build
+            .start_p2p_session(socket)
+            .expect("Session could not be created.");
+
+        let mut driver = Self {
+            session,
+            executor,
+            recorder: None,
+            replay_path: None,
+            debug_overlay: DebugOverlay::new(),
+        };
+        driver.start_recording(settings, handshake.player_ids(&local_id), seed, rules);
+
+        let view = MatchView::new(
+            game_state,
+            handshake.player_names(&local_id),
+            settings.effects,
+        );
+        Match::new(Box::new(driver), view)
+    }
+
+    /// Start recording the replay of the session if enabled in the settings
+    fn start_recording(
+        &mut self,
+        settings: &SessionSettings,
+        player_ids: Vec<String>,
+        seed: u64,
+        rules: MatchRules,
+    ) {
+        let Some(replay_dir) = &settings.replay_dir else {
+            return;
+        };
+
+        let header = ReplayHeader::new(player_ids, settings.fps, seed, rules);
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+
+        self.recorder = Some(ReplayRecorder::new(header));
+        self.replay_path =
+            Some(Path::new(replay_dir).join(format!("replay-{started}.{REPLAY_EXTENSION}")));
+    }
+
+    /// Write the replay of the session, overwriting the one written at the end of the previous match
+    fn save_replay(&self, replay: &Replay) {
+        let Some(path) = &self.replay_path else {
+            return;
+        };
+
+        let saved = match path.parent() {
+            Some(dir) => fs::create_dir_all(dir).map_err(ReplayError::from),
+            None => Ok(()),
+        }
+        .and_then(|_| replay.save(path));
+        match saved {
+            Ok(()) => info!("Replay saved to {}", path.display()),
+            Err(e) => error!("Could not save replay to {}: {e}", path.display()),
+        }
+    }
+}
+
+impl MatchDriver for OnlineDriver {
+    fn poll(&mut self) {
+        self.executor.try_tick();
+        self.session.poll_remote_clients();
+        self.executor.try_tick();
+
+        if is_key_pressed(KeyCode::F3) {
+            self.debug_overlay.toggle();
+        }
+    }
+
+    fn frame_seconds(&self, fps: usize) -> f64 {
+        let seconds = 1. / fps as f64;
+        if self.session.frames_ahead() > 0 {
+            seconds * 1.1
+        } else {
+            seconds
+        }
+    }
+
+    fn advance(
+        &mut self,
+        state: &mut GameState,
+        keys: u8,
+        actions: [u8; NUM_PLAYERS],
+        sounds: &[GameSound],
+    ) -> bool {
+        // frames are only happening if the sessions are synchronized
+        if self.session.current_state() != SessionState::Running {
+            return false;
+        }
+
+        // add input for all local players
+        for handle in self.session.local_player_handles() {
+            self.session
+                .add_local_input(
+                    handle,
+                    Input {
+                        inp: keys | actions[handle],
+                    },
+                )
+                .expect("Invalid player handle");
+        }
+
+        match self.session.advance_frame() {
+            Ok(requests) => {
+                let rollbacks = requests
+                    .iter()
+                    .filter(|request| matches!(request, GGRSRequest::LoadGameState { .. }))
+                    .count();
+                self.debug_overlay.record_rollbacks(rollbacks as u32);
+                state.handle_requests(requests, sounds, self.recorder.as_mut());
+                true
+            }
+            Err(GGRSError::PredictionThreshold) => {
+                self.debug_overlay.record_stall();
+                false
+            }
+            Err(e) => panic!("Unknown error happened during P2PSession::<_>::advance_frame(): {e}"),
+        }
+    }
+
+    fn after_frames(&mut self) {
+        // save the replay once the end of the match is confirmed by all players
+        let confirmed_frame = self.session.confirmed_frame();
+        let finished_replay = self
+            .recorder
+            .as_mut()
+            .and_then(|recorder| recorder.finished_replay(confirmed_frame));
+        if let Some(replay) = finished_replay {
+            self.save_replay(&replay);
+        }
+        self.executor.try_tick();
+    }
+
+    fn local_handle(&self) -> Option<usize> {
+        self.session.local_player_handles().first().copied()
+    }
+
+    fn draw_overlay(&mut self, backend: &MacroquadBackend) {
+        self.debug_overlay.draw(&self.session, backend.font());
+    }
+}
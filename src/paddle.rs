@@ -1,16 +1,16 @@
-use macroquad::{
-    prelude::WHITE,
-    shapes::draw_rectangle,
-    text::{draw_text_ex, Font, TextParams},
-};
+use std::hash::Hasher;
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    traits::{Drawable, Movable},
-    FONT_SIZE, PADDLE_BOTTOM_LIMIT, PADDLE_HEIGHT, PADDLE_TOP_LIMIT, PADDLE_WIDTH, SCORE_MAX_VALUE,
+    helpers::StateHasher,
+    render::CommandList,
+    theme::Theme,
+    traits::{Drawable, Movable, StateHash},
+    FONT_SIZE, PADDLE_BOTTOM_LIMIT, PADDLE_HEIGHT, PADDLE_TOP_LIMIT, PADDLE_WIDTH,
 };
 
-#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default)]
 pub struct Paddle {
     /// Paddle's score
     pub score: i32,
@@ -18,6 +18,8 @@ pub struct Paddle {
     pub pos: i32,
     /// Paddle's velocity
     pub vel: i32,
+    /// Paddle's height, set by the match rules
+    pub height: i32,
 }
 
 impl Paddle {
@@ -32,41 +34,40 @@ impl Paddle {
     /// assert_eq!(paddle.vel, 0);
     /// ```
     pub fn new() -> Self {
+        Self::with_height(PADDLE_HEIGHT as i32)
+    }
+
+    /// Return a new paddle like [`Self::new`], `height` pixels tall
+    pub fn with_height(height: i32) -> Self {
         Paddle {
             score: 0,
             pos: 1,
             vel: 0,
+            height,
         }
     }
 
-    /// Score a point, if paddle's score is [`SCORE_MAX_VALUE`] or greater, then returns true, otherwise false
+    /// Score a point, whether it wins the set is decided by the [`crate::match_rules::MatchRules`]
     /// # Examples
     /// ```
-    /// # use good_game_rusty_pong::{SCORE_MAX_VALUE, paddle::Paddle};
+    /// # use good_game_rusty_pong::paddle::Paddle;
     /// # let mut paddle = Paddle::new();
     /// assert_eq!(paddle.score, 0);
-    /// let is_max_value = paddle.score_point();
+    /// paddle.score_point();
     /// assert_eq!(paddle.score, 1);
-    /// assert_ne!(paddle.score, SCORE_MAX_VALUE);
-    /// assert_eq!(is_max_value, false);
     /// ```
-    pub fn score_point(&mut self) -> bool {
+    pub fn score_point(&mut self) {
         self.score += 1;
-        self.score >= SCORE_MAX_VALUE
     }
 
-    /// Draw the score on the screen using [`draw_text_ex()`] function
-    pub fn draw_score(&self, x: f32, y: f32, font: Font) {
-        draw_text_ex(
-            &self.score.to_string(),
-            x,
-            y,
-            TextParams {
-                font_size: FONT_SIZE,
-                font,
-                ..Default::default()
-            },
-        )
+    /// Highest position of the paddle, so it stays within the screen whatever its height
+    fn top_limit(&self) -> i32 {
+        PADDLE_TOP_LIMIT + PADDLE_HEIGHT as i32 - self.height
+    }
+
+    /// Draw the score with its baseline at y
+    pub fn draw_score(&self, commands: &mut CommandList, theme: &Theme, x: f32, y: f32) {
+        commands.text(&self.score.to_string(), x, y, FONT_SIZE, theme.text);
     }
 }
 
@@ -74,8 +75,9 @@ impl Movable for Paddle {
     /// Move the ball according to its [`Self::vel`]
     /// In case the position is below [`PADDLE_TOP_LIMIT`] and positive velocity
     /// Or in case the position is above [`PADDLE_BOTTOM_LIMIT`] and negative velocity
+    /// The top limit is lowered by as much as the paddle is taller than [`PADDLE_HEIGHT`]
     fn perform_movement(&mut self) {
-        if (self.vel > 0 && self.pos < PADDLE_TOP_LIMIT)
+        if (self.vel > 0 && self.pos < self.top_limit())
             || (self.vel < 0 && self.pos >= PADDLE_BOTTOM_LIMIT)
         {
             self.pos += self.vel;
@@ -83,9 +85,18 @@ impl Movable for Paddle {
     }
 }
 
+impl StateHash for Paddle {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_i32(self.score);
+        hasher.write_i32(self.pos);
+        hasher.write_i32(self.vel);
+        hasher.write_i32(self.height);
+    }
+}
+
 impl Drawable for Paddle {
-    /// Draw the paddle on the screen using the [`draw_rectangle()`] function
-    fn draw(&self, x: f32, y: f32) {
-        draw_rectangle(x, y, PADDLE_WIDTH, PADDLE_HEIGHT, WHITE);
+    /// Draw a rectangle to represent the paddle
+    fn draw(&self, commands: &mut CommandList, theme: &Theme, x: f32, y: f32) {
+        commands.rectangle(x, y, PADDLE_WIDTH, self.height as f32, theme.paddles);
     }
 }
@@ -0,0 +1,182 @@
+use macroquad::prelude::*;
+
+use crate::{
+    game_state::GameState, render::CommandList, theme::Theme, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+
+/// Entry of the pause menu chosen by the local player
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseAction {
+    /// Vote to resume the match
+    Resume,
+    /// Turn the cosmetic effects on or off, only for the local player
+    ToggleEffects,
+    /// Open the settings screen, the match staying paused meanwhile
+    Settings,
+    /// Give up the match for the local player with the handle, the other player wins it
+    Forfeit(usize),
+}
+
+/// Menu shown to the players while the match is paused
+pub struct PauseMenu {
+    /// Entries of the menu, from top to bottom
+    actions: Vec<PauseAction>,
+    /// Index of the highlighted entry in [`Self::actions`]
+    selected: usize,
+}
+
+impl PauseMenu {
+    /// Return the menu of the players of this computer with `local_handles`, who can each forfeit
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::pause_menu::{PauseAction, PauseMenu};
+    /// let menu = PauseMenu::new(&[0, 1]);
+    /// assert_eq!(menu.actions()[3..], [PauseAction::Forfeit(0), PauseAction::Forfeit(1)]);
+    /// ```
+    pub fn new(local_handles: &[usize]) -> Self {
+        let mut actions = vec![
+            PauseAction::Resume,
+            PauseAction::ToggleEffects,
+            PauseAction::Settings,
+        ];
+        actions.extend(local_handles.iter().copied().map(PauseAction::Forfeit));
+        Self {
+            actions,
+            selected: 0,
+        }
+    }
+
+    pub fn actions(&self) -> &[PauseAction] {
+        &self.actions
+    }
+
+    /// Highlight the first entry again, for the next pause
+    pub fn reset(&mut self) {
+        self.selected = 0;
+    }
+
+    /// Move the highlight with UP and DOWN and return the entry chosen with ENTER, if any.
+    /// Like every menu, these keys are fixed, the paddles bound to them do not move while paused.
+    pub fn handle_keys(&mut self) -> Option<PauseAction> {
+        if is_key_pressed(KeyCode::Up) {
+            self.select(-1);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.select(1);
+        }
+
+        if is_key_pressed(KeyCode::Enter) {
+            Some(self.actions[self.selected])
+        } else {
+            None
+        }
+    }
+
+    /// Move the highlight by `step` entries, wrapping around
+    fn select(&mut self, step: isize) {
+        let len = self.actions.len() as isize;
+        self.selected = (self.selected as isize + step).rem_euclid(len) as usize;
+    }
+
+    /// Record the pause screen over the playfield, `names` being ordered by handle.
+    /// Spectators, without `local_handle`, only see who paused the match.
+    pub fn draw(
+        &self,
+        commands: &mut CommandList,
+        theme: &Theme,
+        state: &GameState,
+        names: &[String],
+        local_handle: Option<usize>,
+        effects_enabled: bool,
+    ) {
+        let Some(paused_by) = state.paused_by else {
+            return;
+        };
+        let name_of = |handle: usize| {
+            names
+                .get(handle)
+                .cloned()
+                .unwrap_or_else(|| format!("Player {}", handle + 1))
+        };
+
+        commands.rectangle(
+            0.0,
+            0.0,
+            SCREEN_WIDTH,
+            SCREEN_HEIGHT,
+            Color {
+                a: 0.75,
+                ..theme.background
+            },
+        );
+        let center = SCREEN_WIDTH / 2.0;
+        let title = format!("Paused by {}", name_of(paused_by));
+        commands.centered_text(&title, center, 100.0, 32, theme.text);
+
+        let Some(handle) = local_handle else {
+            return;
+        };
+        // players sharing the keyboard pick who gives up
+        let shared = self
+            .actions
+            .iter()
+            .filter(|action| matches!(action, PauseAction::Forfeit(_)))
+            .count()
+            > 1;
+        for (i, action) in self.actions.iter().enumerate() {
+            let label = match action {
+                PauseAction::Resume => "Resume".to_owned(),
+                PauseAction::ToggleEffects if effects_enabled => "Effects: on".to_owned(),
+                PauseAction::ToggleEffects => "Effects: off".to_owned(),
+                PauseAction::Settings => "Settings".to_owned(),
+                PauseAction::Forfeit(forfeiting) if shared => {
+                    format!("Forfeit as {}", name_of(*forfeiting))
+                }
+                PauseAction::Forfeit(_) => "Forfeit".to_owned(),
+            };
+            let label = if i == self.selected {
+                format!("> {label} <")
+            } else {
+                label
+            };
+            commands.centered_text(&label, center, 160.0 + 32.0 * i as f32, 24, theme.text);
+        }
+
+        let waiting = state
+            .resume_votes
+            .iter()
+            .enumerate()
+            .find(|(other, vote)| *other != handle && !**vote);
+        let status = match waiting {
+            Some((other, _)) if state.resume_votes[handle] => {
+                format!("Waiting for {} to resume...", name_of(other))
+            }
+            _ => format!("Pauses left: {}", state.pauses_left[handle]),
+        };
+        commands.centered_text(&status, center, 280.0, 16, theme.text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_wraps_around() {
+        let mut menu = PauseMenu::new(&[1]);
+        menu.select(-1);
+        assert_eq!(menu.actions[menu.selected], PauseAction::Forfeit(1));
+        menu.select(1);
+        assert_eq!(menu.actions[menu.selected], PauseAction::Resume);
+    }
+
+    #[test]
+    fn spectators_see_no_entries() {
+        let mut state = GameState::new();
+        state.paused_by = Some(0);
+        let mut commands = CommandList::new();
+        PauseMenu::new(&[]).draw(&mut commands, &Theme::classic(), &state, &[], None, true);
+        // the dimmed playfield and the title
+        assert_eq!(commands.commands().len(), 2);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{
+    game_state::{GameState, Side},
+    interpolation::RenderPositions,
+    render::CommandList,
+    theme::Theme,
+    traits::Drawable,
+    NET_DASH_LENGTH, NET_WIDTH, SCORE_POS_X, SCORE_POS_Y, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+
+/// Record the background of the court, its net and its lines if the theme has them
+pub fn draw_court(theme: &Theme, commands: &mut CommandList) {
+    commands.rectangle(0.0, 0.0, SCREEN_WIDTH, SCREEN_HEIGHT, theme.background);
+
+    let net_x = (SCREEN_WIDTH - NET_WIDTH) / 2.0;
+    let mut dash_y = NET_DASH_LENGTH / 2.0;
+    while dash_y < SCREEN_HEIGHT {
+        commands.rectangle(net_x, dash_y, NET_WIDTH, NET_DASH_LENGTH, theme.net);
+        dash_y += NET_DASH_LENGTH * 2.0;
+    }
+
+    if theme.court_lines {
+        commands.rectangle(0.0, 0.0, SCREEN_WIDTH, NET_WIDTH, theme.net);
+        commands.rectangle(
+            0.0,
+            SCREEN_HEIGHT - NET_WIDTH,
+            SCREEN_WIDTH,
+            NET_WIDTH,
+            theme.net,
+        );
+    }
+}
+
+/// Record the paddles and the ball at `positions`, and the scores, in playfield coordinates.
+/// The court is drawn separately with [`draw_court`], so effects can be drawn in between.
+pub fn draw_playfield(
+    state: &GameState,
+    positions: RenderPositions,
+    theme: &Theme,
+    commands: &mut CommandList,
+) {
+    state
+        .left_paddle
+        .draw(commands, theme, 0.0, positions.left_paddle);
+    state
+        .right_paddle
+        .draw(commands, theme, SCREEN_WIDTH - 10.0, positions.right_paddle);
+    state
+        .ball
+        .draw(commands, theme, positions.ball.x, positions.ball.y);
+    state
+        .left_paddle
+        .draw_score(commands, theme, SCORE_POS_X, SCORE_POS_Y);
+    state
+        .right_paddle
+        .draw_score(commands, theme, SCREEN_WIDTH - SCORE_POS_X, SCORE_POS_Y);
+
+    if state.rules.best_of > 1 {
+        let [left_sets, right_sets] = state.sets;
+        let sets_str = format!("Sets {left_sets} - {right_sets}");
+        commands.centered_text(&sets_str, SCREEN_WIDTH / 2.0, SCORE_POS_Y, 20, theme.text);
+    }
+}
+
+/// Record the name of each player below the scores, in the middle of the side they play, `names` being ordered by handle.
+/// The name of the player with `local_handle`, whose keys move the paddle below it, is marked.
+pub fn draw_names(
+    state: &GameState,
+    names: &[String],
+    local_handle: Option<usize>,
+    theme: &Theme,
+    commands: &mut CommandList,
+) {
+    for (handle, name) in names.iter().enumerate() {
+        let x = match state.side_of(handle) {
+            Side::Left => SCREEN_WIDTH / 4.0,
+            Side::Right => SCREEN_WIDTH * 3.0 / 4.0,
+        };
+        let label = if local_handle == Some(handle) {
+            format!("{name} (you)")
+        } else {
+            name.clone()
+        };
+        commands.centered_text(&label, x, SCORE_POS_Y + 18.0, 14, theme.text);
+    }
+}
+
+/// Record the winner and final score of the match, and the options available to the local player, in playfield coordinates
+pub fn draw_results(
+    state: &GameState,
+    winner: Side,
+    local_handle: Option<usize>,
+    theme: &Theme,
+    commands: &mut CommandList,
+) {
+    let winner_str = match winner {
+        Side::Left => "Left paddle wins!",
+        Side::Right => "Right paddle wins!",
+    };
+    let score_str = format!("{} - {}", state.left_paddle.score, state.right_paddle.score);
+    let mut lines = vec![winner_str.to_owned(), score_str];
+    if state.rules.best_of > 1 {
+        let [left_sets, right_sets] = state.sets;
+        lines.push(format!("Sets {left_sets} - {right_sets}"));
+    }
+
+    match local_handle {
+        Some(handle) if state.rematch_votes[handle] => {
+            lines.push("Waiting for the other player...".to_owned())
+        }
+        Some(_) if state.rematch_votes.iter().any(|vote| *vote) => {
+            lines.push("The other player wants a rematch!".to_owned());
+            lines.push("Press R to accept".to_owned());
+        }
+        Some(_) => lines.push("Press R for a rematch".to_owned()),
+        None => {}
+    }
+    lines.push("Press ESC to go back".to_owned());
+
+    commands.rectangle(0.0, 0.0, SCREEN_WIDTH, SCREEN_HEIGHT, theme.background);
+    for (i, line) in lines.iter().enumerate() {
+        commands.text(line, 20.0, SCORE_POS_Y + 32.0 * i as f32, 24, theme.text);
+    }
+}
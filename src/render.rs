@@ -0,0 +1,211 @@
+use macroquad::prelude::*;
+
+use crate::camera::set_playfield_camera;
+
+/// Image the game can draw, each backend loads its own copy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageId {
+    Logo,
+}
+
+/// Where a text is drawn relative to its x position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextAlign {
+    /// The text starts at x
+    Left,
+    /// The text is centered on x
+    Center,
+}
+
+/// Single drawing operation, in the coordinates of whatever the list is drawn to
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCommand {
+    Rectangle {
+        rect: Rect,
+        color: Color,
+    },
+    /// Text with its baseline at y
+    Text {
+        text: String,
+        x: f32,
+        y: f32,
+        font_size: u16,
+        align: TextAlign,
+        color: Color,
+    },
+    Image {
+        image: ImageId,
+        rect: Rect,
+    },
+}
+
+/// Drawing operations recorded by the game, executed in order by a backend:
+/// [`MacroquadBackend`] on screen, or [`crate::software_renderer::SoftwareRenderer`] in memory
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandList {
+    commands: Vec<DrawCommand>,
+}
+
+impl CommandList {
+    /// Return an empty command list
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::render::CommandList;
+    /// # use macroquad::color::WHITE;
+    /// let mut commands = CommandList::new();
+    /// assert!(commands.commands().is_empty());
+    /// commands.rectangle(0.0, 0.0, 10.0, 10.0, WHITE);
+    /// assert_eq!(commands.commands().len(), 1);
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    pub fn rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        self.commands.push(DrawCommand::Rectangle {
+            rect: Rect::new(x, y, w, h),
+            color,
+        });
+    }
+
+    /// Draw `text` starting at x, with its baseline at y
+    pub fn text(&mut self, text: &str, x: f32, y: f32, font_size: u16, color: Color) {
+        self.push_text(text, x, y, font_size, TextAlign::Left, color);
+    }
+
+    /// Draw `text` centered on x, with its baseline at y
+    pub fn centered_text(&mut self, text: &str, x: f32, y: f32, font_size: u16, color: Color) {
+        self.push_text(text, x, y, font_size, TextAlign::Center, color);
+    }
+
+    pub fn image(&mut self, image: ImageId, rect: Rect) {
+        self.commands.push(DrawCommand::Image { image, rect });
+    }
+
+    /// Move everything recorded so far by `offset`
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::render::{CommandList, DrawCommand};
+    /// # use macroquad::{color::WHITE, math::{vec2, Rect}};
+    /// let mut commands = CommandList::new();
+    /// commands.rectangle(1.0, 2.0, 10.0, 10.0, WHITE);
+    /// commands.translate(vec2(3.0, -2.0));
+    /// assert_eq!(
+    ///     commands.commands()[0],
+    ///     DrawCommand::Rectangle { rect: Rect::new(4.0, 0.0, 10.0, 10.0), color: WHITE }
+    /// );
+    /// ```
+    pub fn translate(&mut self, offset: Vec2) {
+        for command in &mut self.commands {
+            match command {
+                DrawCommand::Rectangle { rect, .. } | DrawCommand::Image { rect, .. } => {
+                    rect.x += offset.x;
+                    rect.y += offset.y;
+                }
+                DrawCommand::Text { x, y, .. } => {
+                    *x += offset.x;
+                    *y += offset.y;
+                }
+            }
+        }
+    }
+
+    fn push_text(
+        &mut self,
+        text: &str,
+        x: f32,
+        y: f32,
+        font_size: u16,
+        align: TextAlign,
+        color: Color,
+    ) {
+        self.commands.push(DrawCommand::Text {
+            text: text.to_owned(),
+            x,
+            y,
+            font_size,
+            align,
+            color,
+        });
+    }
+}
+
+/// Draws command lists on screen with macroquad, through whatever camera is set
+pub struct MacroquadBackend {
+    font: Font,
+    logo: Texture2D,
+}
+
+impl MacroquadBackend {
+    pub fn new(font: Font, logo: Texture2D) -> Self {
+        Self { font, logo }
+    }
+
+    /// Font of the texts, for the parts of the screen drawn directly with macroquad
+    pub fn font(&self) -> Font {
+        self.font
+    }
+
+    /// Clear the window and execute `commands` recorded in playfield coordinates, scaled to the window
+    pub fn execute_on_playfield(&self, commands: &CommandList) {
+        clear_background(BLACK);
+        set_playfield_camera();
+        self.execute(commands);
+        set_default_camera();
+    }
+
+    pub fn execute(&self, commands: &CommandList) {
+        for command in commands.commands() {
+            match command {
+                DrawCommand::Rectangle { rect, color } => {
+                    draw_rectangle(rect.x, rect.y, rect.w, rect.h, *color)
+                }
+                DrawCommand::Text {
+                    text,
+                    x,
+                    y,
+                    font_size,
+                    align,
+                    color,
+                } => {
+                    let x = match align {
+                        TextAlign::Left => *x,
+                        TextAlign::Center => {
+                            x - measure_text(text, Some(self.font), *font_size, 1.0).width / 2.0
+                        }
+                    };
+                    draw_text_ex(
+                        text,
+                        x,
+                        *y,
+                        TextParams {
+                            font_size: *font_size,
+                            font: self.font,
+                            color: *color,
+                            ..Default::default()
+                        },
+                    );
+                }
+                DrawCommand::Image { image, rect } => {
+                    let texture = match image {
+                        ImageId::Logo => self.logo,
+                    };
+                    draw_texture_ex(
+                        texture,
+                        rect.x,
+                        rect.y,
+                        WHITE,
+                        DrawTextureParams {
+                            dest_size: Some(vec2(rect.w, rect.h)),
+                            ..Default::default()
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
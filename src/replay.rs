@@ -0,0 +1,575 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use ggrs::{Frame, InputStatus};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game_state::GameState, input::Input, match_rules::MatchRules, BALL_SIZE, EDGE_BOTTOM,
+    EDGE_LEFT, EDGE_RIGHT, EDGE_TOP, INITIAL_VELOCITY, MIDDLE_POS, NUM_PLAYERS,
+    PADDLE_BOTTOM_LIMIT, PADDLE_HEIGHT, PADDLE_TOP_LIMIT, REPLAY_CHECKSUM_INTERVAL,
+    SCORE_MAX_VALUE,
+};
+
+/// First bytes of every replay file
+pub const REPLAY_MAGIC: [u8; 4] = *b"GGRP";
+/// Version of the replay file format, bumped on every incompatible change once replays of a version were shared
+pub const REPLAY_VERSION: u16 = 1;
+/// Extension of the replay files
+pub const REPLAY_EXTENSION: &str = "ggrp";
+
+/// Error happening while reading or writing a replay file
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Format(bincode::Error),
+    /// The file does not start with [`REPLAY_MAGIC`]
+    NotAReplay,
+    /// The file was written with a format version this build cannot read
+    UnsupportedVersion(u16),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "I/O error: {e}"),
+            ReplayError::Format(e) => write!(f, "invalid replay: {e}"),
+            ReplayError::NotAReplay => write!(f, "not a replay file"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "unsupported replay version {version}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+impl From<bincode::Error> for ReplayError {
+    fn from(e: bincode::Error) -> Self {
+        ReplayError::Format(e)
+    }
+}
+
+/// Reason why the simulation of a replay could not be verified against its recording
+#[derive(Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The replay stores no checksums to compare with
+    NoChecksums,
+    /// The simulation diverges from the recording at this frame
+    Diverged(Frame),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::NoChecksums => write!(
+                f,
+                "replay has no stored checksums to compare with, nothing was verified"
+            ),
+            VerifyError::Diverged(frame) => {
+                write!(f, "simulation diverges from the recording at frame {frame}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Gameplay constants the match was simulated with, a replay only plays back identically with the same values
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulationConstants {
+    pub edges: [i32; 4],
+    pub middle_pos: [i32; 2],
+    pub paddle_limits: [i32; 2],
+    pub initial_velocity: i32,
+    pub score_max_value: i32,
+    /// Bit patterns of the `f32` sizes, so they compare exactly
+    pub sizes: [u32; 2],
+}
+
+impl SimulationConstants {
+    /// Return the constants of the running build
+    pub fn current() -> Self {
+        Self {
+            edges: [EDGE_LEFT, EDGE_RIGHT, EDGE_BOTTOM, EDGE_TOP],
+            middle_pos: MIDDLE_POS,
+            paddle_limits: [PADDLE_BOTTOM_LIMIT, PADDLE_TOP_LIMIT],
+            initial_velocity: INITIAL_VELOCITY,
+            score_max_value: SCORE_MAX_VALUE,
+            sizes: [BALL_SIZE.to_bits(), PADDLE_HEIGHT.to_bits()],
+        }
+    }
+}
+
+/// Setup of the recorded session
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub magic: [u8; 4],
+    pub version: u16,
+    /// Version of the game that recorded the replay
+    pub game_version: String,
+    /// Peer ids of the players, ordered by player handle
+    pub players: Vec<String>,
+    /// Frames per second the session ran at
+    pub fps: usize,
+    /// Seed of the session's random source
+    pub seed: u64,
+    pub constants: SimulationConstants,
+    /// Rules the players agreed on
+    pub rules: MatchRules,
+}
+
+impl ReplayHeader {
+    pub fn new(players: Vec<String>, fps: usize, seed: u64, rules: MatchRules) -> Self {
+        Self {
+            magic: REPLAY_MAGIC,
+            version: REPLAY_VERSION,
+            game_version: env!("CARGO_PKG_VERSION").to_owned(),
+            players,
+            fps,
+            seed,
+            constants: SimulationConstants::current(),
+            rules,
+        }
+    }
+}
+
+/// Start of every replay file, read first to reject unsupported files before decoding the rest
+#[derive(Deserialize)]
+struct ReplayPrelude {
+    magic: [u8; 4],
+    version: u16,
+}
+
+/// A recorded session: its setup and the confirmed inputs of every frame
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    /// Input bits of every player handle, indexed by frame
+    pub inputs: Vec<[u8; NUM_PLAYERS]>,
+    /// Checksums of the game state every [`REPLAY_CHECKSUM_INTERVAL`] frames and at the last frame
+    pub checksums: Vec<(Frame, u64)>,
+}
+
+/// Outcome of simulating all the inputs of a replay
+pub struct ReplayRun {
+    /// Game state after the last frame
+    pub state: GameState,
+    /// Checksums of the game state every [`REPLAY_CHECKSUM_INTERVAL`] frames and at the last frame
+    pub checksums: Vec<(Frame, u64)>,
+}
+
+impl ReplayRun {
+    /// Return the first frame whose checksum differs from `expected`, or is missing from either
+    pub fn first_mismatch(&self, expected: &[(Frame, u64)]) -> Option<Frame> {
+        let mismatch = self
+            .checksums
+            .iter()
+            .zip(expected)
+            .find(|(actual, expected)| actual != expected);
+        match mismatch {
+            Some((actual, expected)) => Some(actual.0.min(expected.0)),
+            None if self.checksums.len() > expected.len() => Some(self.checksums[expected.len()].0),
+            None if self.checksums.len() < expected.len() => Some(expected[self.checksums.len()].0),
+            None => None,
+        }
+    }
+}
+
+impl Replay {
+    /// Return a replay of `inputs`, with the `checksums` the session saved while playing them
+    pub fn new(
+        header: ReplayHeader,
+        inputs: Vec<[u8; NUM_PLAYERS]>,
+        checksums: Vec<(Frame, u64)>,
+    ) -> Self {
+        Self {
+            header,
+            inputs,
+            checksums,
+        }
+    }
+
+    /// Run all the inputs through the simulation from a new game state set up like the session,
+    /// without playing sounds
+    pub fn simulate(&self) -> ReplayRun {
+        let mut state = GameState::for_match(self.header.rules, self.header.seed);
+        let mut checksums = Vec::new();
+        for frame in 0..self.inputs.len() {
+            let inputs = self
+                .frame_inputs(frame)
+                .expect("Frame is within the replay.");
+            state.advance(inputs, &[]);
+            if is_checksum_frame(state.frame(), self.inputs.len()) {
+                checksums.push((state.frame(), state.checksum()));
+            }
+        }
+        ReplayRun { state, checksums }
+    }
+
+    /// Compare the checksums of a `run` of the replay with the ones it stores,
+    /// and return how many matched
+    pub fn verify(&self, run: &ReplayRun) -> Result<usize, VerifyError> {
+        if self.checksums.is_empty() {
+            return Err(VerifyError::NoChecksums);
+        }
+        match run.first_mismatch(&self.checksums) {
+            Some(frame) => Err(VerifyError::Diverged(frame)),
+            None => Ok(self.checksums.len()),
+        }
+    }
+
+    /// Return the recorded inputs of `frame` in the form [`crate::game_state::GameState::advance`] expects
+    pub fn frame_inputs(&self, frame: usize) -> Option<Vec<(Input, InputStatus)>> {
+        let inputs = self.inputs.get(frame)?;
+        Some(
+            inputs
+                .iter()
+                .map(|inp| (Input { inp: *inp }, InputStatus::Confirmed))
+                .collect(),
+        )
+    }
+
+    /// Encode the replay into bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ReplayError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Decode a replay from bytes, checking its magic and version first
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let prelude: ReplayPrelude = bincode::deserialize(bytes)?;
+        if prelude.magic != REPLAY_MAGIC {
+            return Err(ReplayError::NotAReplay);
+        }
+        match prelude.version {
+            REPLAY_VERSION => Ok(bincode::deserialize(bytes)?),
+            version => Err(ReplayError::UnsupportedVersion(version)),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+/// Return true if the checksum of `frame` is stored in a replay of `length` frames:
+/// every [`REPLAY_CHECKSUM_INTERVAL`] frames and at the last frame
+fn is_checksum_frame(frame: Frame, length: usize) -> bool {
+    frame > 0
+        && ((frame as usize).is_multiple_of(REPLAY_CHECKSUM_INTERVAL) || frame as usize == length)
+}
+
+/// Return the replay files in `dir`, the most recently modified first
+pub fn list_replays(dir: impl AsRef<Path>) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut replays: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|ext| ext == REPLAY_EXTENSION)
+        })
+        .map(|entry| {
+            let modified = entry.metadata().and_then(|meta| meta.modified()).ok();
+            (modified, entry.path())
+        })
+        .collect();
+    replays.sort_by(|a, b| b.cmp(a));
+    replays.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Records the inputs of a running session and the checksums of the states it saved, following its rollbacks,
+/// so only confirmed inputs and checksums are saved
+pub struct ReplayRecorder {
+    header: ReplayHeader,
+    /// Latest inputs of every simulated frame, indexed by frame
+    inputs: Vec<[u8; NUM_PLAYERS]>,
+    /// Latest checksum of every saved frame, in frame order
+    checksums: Vec<(Frame, u64)>,
+    /// Frames at which a match of the session ended
+    match_ends: Vec<Frame>,
+    /// Amount of ended matches already returned by [`Self::finished_replay`]
+    saved_matches: usize,
+}
+
+impl ReplayRecorder {
+    pub fn new(header: ReplayHeader) -> Self {
+        Self {
+            header,
+            inputs: Vec::new(),
+            checksums: Vec::new(),
+            match_ends: Vec::new(),
+            saved_matches: 0,
+        }
+    }
+
+    /// Forget everything simulated after `frame`, which GGRS rolled back to
+    pub fn rollback(&mut self, frame: Frame) {
+        self.inputs.truncate(frame.max(0) as usize);
+        self.checksums.retain(|(saved, _)| *saved <= frame);
+        self.match_ends.retain(|end| *end <= frame);
+    }
+
+    /// Record the checksum of the game state the session saved at `frame`,
+    /// replacing the ones of `frame` and later frames saved before a rollback
+    pub fn save_checksum(&mut self, frame: Frame, checksum: u64) {
+        while self
+            .checksums
+            .last()
+            .is_some_and(|(saved, _)| *saved >= frame)
+        {
+            self.checksums.pop();
+        }
+        self.checksums.push((frame, checksum));
+    }
+
+    /// Record the inputs used to advance from `frame`
+    pub fn record(&mut self, frame: Frame, inputs: &[(Input, InputStatus)]) {
+        self.inputs.truncate(frame.max(0) as usize);
+        let mut bits = [0; NUM_PLAYERS];
+        for (bit, (input, status)) in bits.iter_mut().zip(inputs) {
+            if !matches!(status, InputStatus::Disconnected) {
+                *bit = input.inp;
+            }
+        }
+        self.inputs.push(bits);
+    }
+
+    /// Mark that a match ended once the session reached `frame`
+    pub fn match_ended(&mut self, frame: Frame) {
+        self.match_ends.push(frame);
+    }
+
+    /// Return the replay of the session up to the end of its latest match,
+    /// if that match ended at or before `confirmed_frame` and was not returned before
+    pub fn finished_replay(&mut self, confirmed_frame: Frame) -> Option<Replay> {
+        let end = *self.match_ends.last()?;
+        if self.match_ends.len() <= self.saved_matches || end > confirmed_frame {
+            return None;
+        }
+
+        self.saved_matches = self.match_ends.len();
+        let checksums = self
+            .checksums
+            .iter()
+            .filter(|(frame, _)| *frame <= end && is_checksum_frame(*frame, end as usize))
+            .copied()
+            .collect();
+        Some(Replay::new(
+            self.header.clone(),
+            self.inputs[..end as usize].to_vec(),
+            checksums,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{match_rules::MatchPreset, rng::Rng};
+
+    fn header() -> ReplayHeader {
+        ReplayHeader::new(
+            vec!["a".to_owned(), "b".to_owned()],
+            60,
+            0,
+            MatchRules::classic(),
+        )
+    }
+
+    /// Return a replay of `inputs` with the checksums of a session that simulated them like [`Replay::simulate`]
+    fn simulated(inputs: Vec<[u8; NUM_PLAYERS]>) -> Replay {
+        let mut replay = Replay::new(header(), inputs, Vec::new());
+        replay.checksums = replay.simulate().checksums;
+        replay
+    }
+
+    fn inputs(first: u8, second: u8) -> Vec<(Input, InputStatus)> {
+        vec![
+            (Input { inp: first }, InputStatus::Confirmed),
+            (Input { inp: second }, InputStatus::Predicted),
+        ]
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let replay = simulated(vec![[1, 2], [3, 4]]);
+        let bytes = replay.to_bytes().unwrap();
+        assert_eq!(Replay::from_bytes(&bytes).unwrap(), replay);
+    }
+
+    #[test]
+    fn checksums_every_interval_and_last_frame() {
+        let replay = simulated(vec![[1, 8]; REPLAY_CHECKSUM_INTERVAL * 2 + 1]);
+        let frames: Vec<Frame> = replay.checksums.iter().map(|(frame, _)| *frame).collect();
+        let interval = REPLAY_CHECKSUM_INTERVAL as Frame;
+        assert_eq!(frames, vec![interval, interval * 2, interval * 2 + 1]);
+    }
+
+    #[test]
+    fn simulation_matches_stored_checksums() {
+        let replay = simulated(vec![[1, 8]; REPLAY_CHECKSUM_INTERVAL * 3]);
+        assert_eq!(replay.simulate().first_mismatch(&replay.checksums), None);
+        assert_eq!(replay.verify(&replay.simulate()), Ok(3));
+    }
+
+    #[test]
+    fn nothing_to_verify_without_checksums() {
+        let replay = Replay::new(header(), vec![[1, 8]; 3], Vec::new());
+        assert_eq!(
+            replay.verify(&replay.simulate()),
+            Err(VerifyError::NoChecksums)
+        );
+    }
+
+    #[test]
+    fn detect_diverging_simulation() {
+        let mut replay = simulated(vec![[1, 8]; REPLAY_CHECKSUM_INTERVAL * 3]);
+        for inputs in &mut replay.inputs[REPLAY_CHECKSUM_INTERVAL + 1..] {
+            *inputs = [2, 4];
+        }
+        let interval = REPLAY_CHECKSUM_INTERVAL as Frame;
+        assert_eq!(
+            replay.simulate().first_mismatch(&replay.checksums),
+            Some(interval * 2)
+        );
+    }
+
+    #[test]
+    fn simulation_follows_rules() {
+        let mut replay = simulated(vec![[0, 0]; 3]);
+        replay.header.rules = MatchPreset::Fast.rules();
+        let state = replay.simulate().state;
+        assert_eq!(state.ball.pos_x, 3 * MatchPreset::Fast.rules().ball_speed);
+    }
+
+    #[test]
+    fn simulation_starts_from_seed() {
+        let mut replay = simulated(vec![[1, 8]; 3]);
+        replay.header.seed = 1;
+        assert_eq!(replay.simulate().state.rng, Rng::new(1));
+        assert_eq!(replay.simulate().first_mismatch(&replay.checksums), Some(3));
+    }
+
+    #[test]
+    fn reject_other_files() {
+        let result = Replay::from_bytes(b"not a replay file at all");
+        assert!(matches!(result, Err(ReplayError::NotAReplay)));
+    }
+
+    #[test]
+    fn reject_newer_version() {
+        let mut replay = simulated(Vec::new());
+        replay.header.version = REPLAY_VERSION + 1;
+        let bytes = replay.to_bytes().unwrap();
+        assert!(matches!(
+            Replay::from_bytes(&bytes),
+            Err(ReplayError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn rollback_overwrites_predicted_inputs() {
+        let mut recorder = ReplayRecorder::new(header());
+        recorder.record(0, &inputs(1, 0));
+        recorder.record(1, &inputs(1, 0));
+        recorder.rollback(1);
+        recorder.record(1, &inputs(1, 8));
+        recorder.match_ended(2);
+
+        let replay = recorder.finished_replay(2).unwrap();
+        assert_eq!(replay.inputs, vec![[1, 0], [1, 8]]);
+    }
+
+    #[test]
+    fn wait_for_confirmed_match_end() {
+        let mut recorder = ReplayRecorder::new(header());
+        recorder.record(0, &inputs(1, 0));
+        recorder.match_ended(1);
+        assert!(recorder.finished_replay(0).is_none());
+        assert!(recorder.finished_replay(1).is_some());
+        assert!(recorder.finished_replay(2).is_none());
+    }
+
+    /// Run `frames` frames of a session that saves every state, like GGRS, into `recorder`
+    fn record_session(recorder: &mut ReplayRecorder, frames: usize) -> GameState {
+        let mut state = GameState::new();
+        for _ in 0..frames {
+            recorder.save_checksum(state.frame(), state.checksum());
+            recorder.record(state.frame(), &inputs(1, 8));
+            state.advance(inputs(1, 8), &[]);
+        }
+        recorder.save_checksum(state.frame(), state.checksum());
+        state
+    }
+
+    #[test]
+    fn saved_checksums_are_stored() {
+        let mut recorder = ReplayRecorder::new(header());
+        let state = record_session(&mut recorder, REPLAY_CHECKSUM_INTERVAL + 5);
+        recorder.match_ended(state.frame());
+
+        let replay = recorder.finished_replay(state.frame()).unwrap();
+        let interval = REPLAY_CHECKSUM_INTERVAL as Frame;
+        assert_eq!(
+            replay.checksums,
+            vec![
+                (interval, replay.checksums[0].1),
+                (interval + 5, state.checksum())
+            ]
+        );
+        assert_eq!(replay.simulate().first_mismatch(&replay.checksums), None);
+    }
+
+    #[test]
+    fn desync_of_the_session_is_detected() {
+        let mut recorder = ReplayRecorder::new(header());
+        let state = record_session(&mut recorder, REPLAY_CHECKSUM_INTERVAL);
+        // the session diverged from what its inputs simulate
+        recorder.save_checksum(state.frame(), state.checksum() + 1);
+        recorder.match_ended(state.frame());
+
+        let replay = recorder.finished_replay(state.frame()).unwrap();
+        assert_eq!(
+            replay.simulate().first_mismatch(&replay.checksums),
+            Some(state.frame())
+        );
+    }
+
+    #[test]
+    fn rollback_replaces_saved_checksums() {
+        let mut recorder = ReplayRecorder::new(header());
+        recorder.save_checksum(1, 10);
+        recorder.save_checksum(2, 20);
+        recorder.save_checksum(3, 30);
+        recorder.rollback(1);
+        recorder.save_checksum(2, 21);
+        assert_eq!(recorder.checksums, vec![(1, 10), (2, 21)]);
+    }
+
+    #[test]
+    fn rolled_back_match_end_is_forgotten() {
+        let mut recorder = ReplayRecorder::new(header());
+        recorder.record(0, &inputs(1, 0));
+        recorder.record(1, &inputs(1, 0));
+        recorder.match_ended(2);
+        recorder.rollback(1);
+        assert!(recorder.finished_replay(5).is_none());
+    }
+}
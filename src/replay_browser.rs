@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use macroquad::prelude::*;
+
+use crate::{
+    render::CommandList,
+    replay::list_replays,
+    replay_screen::ReplayScreen,
+    screen::{Context, Screen, Transition},
+    REPLAY_BROWSER_ROWS,
+};
+
+/// Screen listing the replay files of the replay directory, the most recent first
+pub struct ReplayBrowser {
+    dir: String,
+    replays: Vec<PathBuf>,
+    /// Index of the highlighted replay
+    selected: usize,
+}
+
+impl ReplayBrowser {
+    pub fn new(dir: &str) -> Self {
+        Self {
+            dir: dir.to_owned(),
+            replays: list_replays(dir),
+            selected: 0,
+        }
+    }
+
+    /// Record the list of replays in window coordinates
+    pub fn draw(&self, commands: &mut CommandList) {
+        let title = format!("Replays in {}", self.dir);
+        commands.text(&title, 20.0, 60.0, 30, WHITE);
+
+        if self.replays.is_empty() {
+            commands.text("No replay found", 20.0, 110.0, 24, GRAY);
+        }
+        // keep the highlighted replay in the visible part of the list
+        let first = self.selected.saturating_sub(REPLAY_BROWSER_ROWS - 1);
+        let visible = self.replays.iter().enumerate().skip(first);
+        for (row, (i, path)) in visible.take(REPLAY_BROWSER_ROWS).enumerate() {
+            let name = path
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+            let (label, color) = if i == self.selected {
+                (format!("> {name}"), WHITE)
+            } else {
+                (format!("  {name}"), GRAY)
+            };
+            commands.text(&label, 20.0, 110.0 + 32.0 * row as f32, 24, color);
+        }
+
+        commands.text(
+            "UP/DOWN to choose, ENTER to watch, ESC to go back",
+            20.0,
+            110.0 + 32.0 * (REPLAY_BROWSER_ROWS + 1) as f32,
+            24,
+            WHITE,
+        );
+    }
+}
+
+impl Screen for ReplayBrowser {
+    fn update(&mut self, _ctx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if is_key_pressed(KeyCode::Down) && self.selected + 1 < self.replays.len() {
+            self.selected += 1;
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            return Transition::Pop;
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            let screen = self
+                .replays
+                .get(self.selected)
+                .and_then(|path| ReplayScreen::open(path));
+            if let Some(screen) = screen {
+                return Transition::Push(Box::new(screen));
+            }
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        let mut commands = CommandList::new();
+        self.draw(&mut commands);
+        ctx.backend.execute(&commands);
+    }
+}
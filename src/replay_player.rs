@@ -0,0 +1,224 @@
+use macroquad::prelude::*;
+
+use crate::{
+    game_state::GameState, replay::Replay, sound::GameSound, REPLAY_SEEK_SECONDS,
+    REPLAY_SNAPSHOT_INTERVAL, REPLAY_SPEEDS,
+};
+
+/// Plays back a [`Replay`] by feeding its recorded inputs into [`GameState::advance`]
+pub struct ReplayPlayer {
+    replay: Replay,
+    /// Game states at every [`REPLAY_SNAPSHOT_INTERVAL`] frames, used to seek backward
+    snapshots: Vec<GameState>,
+    /// True if the playback is paused
+    paused: bool,
+    /// Index of the playback speed in [`REPLAY_SPEEDS`]
+    speed_index: usize,
+    /// Frames to simulate that did not fit in the previous updates
+    pending_frames: f64,
+}
+
+impl ReplayPlayer {
+    /// Return a player at the first frame of `replay`, which `state` is reset to
+    pub fn new(replay: Replay, state: &mut GameState) -> Self {
+        *state = GameState::for_match(replay.header.rules, replay.header.seed);
+        Self {
+            replay,
+            snapshots: vec![*state],
+            paused: false,
+            speed_index: REPLAY_SPEEDS
+                .iter()
+                .position(|speed| *speed == 1.0)
+                .unwrap_or(0),
+            pending_frames: 0.0,
+        }
+    }
+
+    /// Amount of frames in the replay
+    pub fn total_frames(&self) -> usize {
+        self.replay.inputs.len()
+    }
+
+    /// Playback speed multiplier
+    pub fn speed(&self) -> f64 {
+        REPLAY_SPEEDS[self.speed_index]
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.pending_frames = 0.0;
+    }
+
+    pub fn speed_up(&mut self) {
+        self.speed_index = (self.speed_index + 1).min(REPLAY_SPEEDS.len() - 1);
+    }
+
+    pub fn slow_down(&mut self) {
+        self.speed_index = self.speed_index.saturating_sub(1);
+    }
+
+    /// Advance the playback by `delta` seconds of real time, scaled by the playback speed.
+    /// The playback pauses by itself at the end of the replay.
+    pub fn update(&mut self, state: &mut GameState, delta: f64, sounds: &[GameSound]) {
+        if self.paused {
+            return;
+        }
+
+        self.pending_frames += delta * self.replay.header.fps as f64 * self.speed();
+        while self.pending_frames >= 1.0 {
+            self.pending_frames -= 1.0;
+            if !self.step(state, sounds) {
+                self.paused = true;
+                self.pending_frames = 0.0;
+                break;
+            }
+        }
+    }
+
+    /// Advance a single frame, returning false if the end of the replay was reached
+    pub fn step(&mut self, state: &mut GameState, sounds: &[GameSound]) -> bool {
+        let frame = state.frame() as usize;
+        let Some(inputs) = self.replay.frame_inputs(frame) else {
+            return false;
+        };
+
+        state.advance(inputs, sounds);
+
+        let next_frame = frame + 1;
+        if next_frame.is_multiple_of(REPLAY_SNAPSHOT_INTERVAL)
+            && self.snapshots.len() == next_frame / REPLAY_SNAPSHOT_INTERVAL
+        {
+            self.snapshots.push(*state);
+        }
+        true
+    }
+
+    /// Move the playback to `frame`, from the closest earlier snapshot, without playing sounds
+    pub fn seek(&mut self, state: &mut GameState, frame: usize) {
+        let frame = frame.min(self.total_frames());
+        let snapshot = (frame / REPLAY_SNAPSHOT_INTERVAL).min(self.snapshots.len() - 1);
+        if frame < state.frame() as usize
+            || snapshot * REPLAY_SNAPSHOT_INTERVAL > state.frame() as usize
+        {
+            *state = self.snapshots[snapshot];
+        }
+
+        while (state.frame() as usize) < frame && self.step(state, &[]) {}
+        self.pending_frames = 0.0;
+    }
+
+    /// Handle the playback controls of the keyboard.
+    /// Return false if the player asked to leave the replay.
+    pub fn handle_keys(&mut self, state: &mut GameState, sounds: &[GameSound]) -> bool {
+        let seek_frames = REPLAY_SEEK_SECONDS * self.replay.header.fps;
+        let frame = state.frame() as usize;
+
+        if is_key_pressed(KeyCode::Space) {
+            self.toggle_pause();
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.speed_up();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.slow_down();
+        }
+        if is_key_pressed(KeyCode::Left) {
+            self.seek(state, frame.saturating_sub(seek_frames));
+        }
+        if is_key_pressed(KeyCode::Right) {
+            self.seek(state, frame + seek_frames);
+        }
+        if is_key_pressed(KeyCode::Home) {
+            self.seek(state, 0);
+        }
+        if is_key_pressed(KeyCode::Period) && self.paused {
+            self.step(state, sounds);
+        }
+
+        !is_key_pressed(KeyCode::Escape)
+    }
+
+    /// Draw the playback position, speed and controls at the bottom of the screen
+    pub fn draw_status(&self, state: &GameState, font: Font) {
+        let status = format!(
+            "{} {} / {} x{}",
+            if self.paused { "PAUSED" } else { "PLAYING" },
+            state.frame(),
+            self.total_frames(),
+            self.speed()
+        );
+        let controls = "SPACE pause . step UP/DOWN speed LEFT/RIGHT seek ESC exit";
+        for (i, line) in [status.as_str(), controls].iter().enumerate() {
+            draw_text_ex(
+                line,
+                4.0,
+                screen_height() - 22.0 + 14.0 * i as f32,
+                TextParams {
+                    font_size: 12,
+                    font,
+                    color: GRAY,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{match_rules::MatchRules, replay::ReplayHeader, INPUT_LEFT_PADDLE_DOWN};
+
+    fn replay(frames: usize) -> Replay {
+        Replay::new(
+            ReplayHeader::new(
+                vec!["a".to_owned(), "b".to_owned()],
+                60,
+                0,
+                MatchRules::classic(),
+            ),
+            vec![[INPUT_LEFT_PADDLE_DOWN, 0]; frames],
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn stop_at_end_of_replay() {
+        let mut state = GameState::new();
+        let mut player = ReplayPlayer::new(replay(3), &mut state);
+        player.update(&mut state, 1.0, &[]);
+        assert_eq!(state.frame(), 3);
+        assert!(player.is_paused());
+    }
+
+    #[test]
+    fn half_speed_plays_half_the_frames() {
+        let mut state = GameState::new();
+        let mut player = ReplayPlayer::new(replay(100), &mut state);
+        player.slow_down();
+        player.update(&mut state, 1.0, &[]);
+        assert_eq!(player.speed(), 0.5);
+        assert_eq!(state.frame(), 30);
+    }
+
+    #[test]
+    fn seek_backward_matches_straight_playback() {
+        let mut state = GameState::new();
+        let mut player = ReplayPlayer::new(replay(REPLAY_SNAPSHOT_INTERVAL * 3), &mut state);
+        player.seek(&mut state, REPLAY_SNAPSHOT_INTERVAL * 2 + 10);
+        player.seek(&mut state, REPLAY_SNAPSHOT_INTERVAL + 5);
+
+        let mut expected = GameState::new();
+        let mut straight = ReplayPlayer::new(replay(REPLAY_SNAPSHOT_INTERVAL * 3), &mut expected);
+        straight.seek(&mut expected, REPLAY_SNAPSHOT_INTERVAL + 5);
+
+        assert_eq!(state.frame(), expected.frame());
+        assert_eq!(state.ball.pos_x, expected.ball.pos_x);
+        assert_eq!(state.ball.pos_y, expected.ball.pos_y);
+        assert_eq!(state.left_paddle.pos, expected.left_paddle.pos);
+    }
+}
@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use instant::Instant;
+use macroquad::prelude::*;
+
+use crate::{
+    game_state::GameState,
+    match_screen::MatchView,
+    pause_menu::PauseMenu,
+    playfield::draw_results,
+    render::CommandList,
+    replay::Replay,
+    replay_player::ReplayPlayer,
+    screen::{Context, Screen, Transition},
+};
+
+/// Screen playing back a recorded match
+pub struct ReplayScreen {
+    player: ReplayPlayer,
+    view: MatchView,
+    /// Never opened, only draws who paused the recorded match
+    pause_menu: PauseMenu,
+    last_update: Instant,
+}
+
+impl ReplayScreen {
+    /// Load the replay file at `path` to play it back, or log why it could not be loaded
+    pub fn open(path: &Path) -> Option<Self> {
+        match Replay::load(path) {
+            Ok(replay) => {
+                info!("Playing replay {}", path.display());
+                let mut game_state = GameState::new();
+                let player = ReplayPlayer::new(replay, &mut game_state);
+                Some(Self {
+                    player,
+                    // the effects follow consecutive frames, which seeking skips
+                    view: MatchView::new(game_state, Vec::new(), false),
+                    pause_menu: PauseMenu::new(&[]),
+                    last_update: Instant::now(),
+                })
+            }
+            Err(e) => {
+                error!("Could not load replay {}: {e}", path.display());
+                None
+            }
+        }
+    }
+}
+
+impl Screen for ReplayScreen {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        let delta = Instant::now().duration_since(self.last_update);
+        self.last_update = Instant::now();
+
+        let state = &mut self.view.game_state;
+        let keep_playing = self.player.handle_keys(state, &ctx.sounds);
+        self.player.update(state, delta.as_secs_f64(), &ctx.sounds);
+
+        if keep_playing {
+            Transition::None
+        } else {
+            Transition::Pop
+        }
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        let state = &self.view.game_state;
+        let mut commands = CommandList::new();
+        match state.winner {
+            Some(winner) => draw_results(state, winner, None, &ctx.theme, &mut commands),
+            // the playback already steps frames at its own speed, it is drawn without interpolation
+            None => self
+                .view
+                .draw(&mut commands, &ctx.theme, None, 1.0, &self.pause_menu),
+        }
+        ctx.backend.execute_on_playfield(&commands);
+        self.player.draw_status(state, ctx.backend.font());
+    }
+}
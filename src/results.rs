@@ -0,0 +1,63 @@
+use macroquad::prelude::*;
+
+use crate::{
+    match_screen::{Match, MatchScreen},
+    playfield::draw_results,
+    render::CommandList,
+    screen::{Context, Screen, Transition},
+};
+
+/// Screen of a finished match, which keeps running to collect the rematch votes
+pub struct ResultsScreen {
+    /// The match, only taken when a rematch starts
+    game: Option<Match>,
+}
+
+impl ResultsScreen {
+    pub fn new(game: Match) -> Self {
+        Self { game: Some(game) }
+    }
+}
+
+impl Screen for ResultsScreen {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        let game = self
+            .game
+            .as_mut()
+            .expect("The match is only taken when leaving the screen.");
+        game.update(ctx);
+
+        // the match restarted once every player voted for a rematch
+        if game.game_state().winner.is_none() {
+            let game = self.game.take().expect("The match was just updated.");
+            return Transition::Replace(Box::new(MatchScreen::new(game)));
+        }
+
+        // players can leave once the match is over
+        if is_key_pressed(KeyCode::Escape) {
+            return Transition::Pop;
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        let Some(game) = &mut self.game else {
+            return;
+        };
+        let state = game.game_state();
+        let Some(winner) = state.winner else {
+            return;
+        };
+
+        let mut commands = CommandList::new();
+        draw_results(
+            state,
+            winner,
+            game.local_handle(),
+            &ctx.theme,
+            &mut commands,
+        );
+        ctx.backend.execute_on_playfield(&commands);
+        game.render_overlay(ctx);
+    }
+}
@@ -0,0 +1,91 @@
+use std::hash::Hasher;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{helpers::StateHasher, traits::StateHash};
+
+/// Deterministic pseudo random number generator (splitmix64).
+/// Its state lives in the [`crate::game_state::GameState`], so it is saved, loaded and checksummed with it,
+/// and every peer draws the same numbers on the same frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Return a generator seeded with `seed`
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::rng::Rng;
+    /// let mut first = Rng::new(42);
+    /// let mut second = Rng::new(42);
+    /// assert_eq!(first.next_u64(), second.next_u64());
+    /// ```
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Return a number in the range `min..max`, panicking if the range is empty
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        assert!(min < max, "Empty random range {min}..{max}.");
+        let span = (max as i64 - min as i64) as u128;
+        let offset = (self.next_u64() as u128 * span) >> 64;
+        (min as i64 + offset as i64) as i32
+    }
+
+    pub fn next_bool(&mut self) -> bool {
+        self.next_u64() >> 63 == 1
+    }
+}
+
+impl StateHash for Rng {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_u64(self.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut first = Rng::new(7);
+        let mut second = Rng::new(7);
+        for _ in 0..100 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+    }
+
+    #[test]
+    fn different_seeds_differ() {
+        let first: Vec<u64> = (0..4)
+            .scan(Rng::new(1), |rng, _| Some(rng.next_u64()))
+            .collect();
+        let second: Vec<u64> = (0..4)
+            .scan(Rng::new(2), |rng, _| Some(rng.next_u64()))
+            .collect();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn range_stays_in_bounds() {
+        let mut rng = Rng::new(0);
+        let mut seen = [false; 5];
+        for _ in 0..1000 {
+            let value = rng.range(-2, 3);
+            assert!((-2..3).contains(&value));
+            seen[(value + 2) as usize] = true;
+        }
+        assert!(seen.iter().all(|seen| *seen));
+        assert_eq!(rng.range(i32::MIN, i32::MIN + 1), i32::MIN);
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt;
+
+use crate::{rng::Rng, ROOM_CODE_LENGTH, ROOM_NAME_MAX_LENGTH};
+
+/// Characters of the generated room codes, leaving out the ones easily mistaken for each other like `0` and `O`
+const ROOM_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+/// Matchbox room to look for the other peers in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Room {
+    /// Room shared with friends who typed the same name
+    Named(String),
+    /// Whichever player the server pairs us with
+    Random,
+}
+
+/// Reason a room name was refused
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoomNameError {
+    Empty,
+    TooLong,
+    NotAllowed(char),
+}
+
+impl fmt::Display for RoomNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoomNameError::Empty => write!(f, "The room name is empty"),
+            RoomNameError::TooLong => write!(
+                f,
+                "Room names have at most {ROOM_NAME_MAX_LENGTH} characters"
+            ),
+            RoomNameError::NotAllowed(c) => write!(
+                f,
+                "Room names only have letters, digits and dashes, not {c:?}"
+            ),
+        }
+    }
+}
+
+/// Return true if `c` can be part of a room name
+pub fn is_room_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-'
+}
+
+impl Room {
+    /// Return the room called `name`, ignoring the surrounding whitespace and the case of the letters
+    pub fn named(name: &str) -> Result<Self, RoomNameError> {
+        // uppercasing can lengthen a name, like "ß" turning into "SS", so the checked name is the uppercased one
+        let name = name.trim().to_uppercase();
+        if name.is_empty() {
+            Err(RoomNameError::Empty)
+        } else if name.chars().count() > ROOM_NAME_MAX_LENGTH {
+            Err(RoomNameError::TooLong)
+        } else if let Some(c) = name.chars().find(|c| !is_room_name_char(*c)) {
+            Err(RoomNameError::NotAllowed(c))
+        } else {
+            Ok(Room::Named(name))
+        }
+    }
+
+    /// Return a private room with a code of [`ROOM_CODE_LENGTH`] random characters, split in two by a dash
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::room::Room;
+    /// let Room::Named(code) = Room::private(42) else { unreachable!() };
+    /// assert_eq!(code.len(), 9);
+    /// assert_eq!(Room::named(&code.to_lowercase()), Ok(Room::private(42)));
+    /// ```
+    pub fn private(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut code = String::with_capacity(ROOM_CODE_LENGTH + 1);
+        for i in 0..ROOM_CODE_LENGTH {
+            if i == ROOM_CODE_LENGTH / 2 {
+                code.push('-');
+            }
+            let index = rng.range(0, ROOM_CODE_ALPHABET.len() as i32) as usize;
+            code.push(ROOM_CODE_ALPHABET[index] as char);
+        }
+        Room::Named(code)
+    }
+
+    /// Return the URL of the room on the matchbox `server`.
+    /// Rooms are prefixed with `macro` so that the 4 digits ones stay compatible with older versions.
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::room::Room;
+    /// let server = "wss://match.gschup.dev";
+    /// assert_eq!(Room::named("1234").unwrap().url(server), "wss://match.gschup.dev/macro1234");
+    /// assert_eq!(Room::named("café").unwrap().url(server), "wss://match.gschup.dev/macroCAF%C3%89");
+    /// assert_eq!(Room::Random.url(server), "wss://match.gschup.dev/macro?next=2");
+    /// ```
+    pub fn url(&self, server: &str) -> String {
+        match self {
+            Room::Named(name) => format!("{server}/macro{}", percent_encode(name)),
+            Room::Random => format!("{server}/macro?next=2"),
+        }
+    }
+}
+
+impl fmt::Display for Room {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Room::Named(name) => write!(f, "room {name}"),
+            Room::Random => write!(f, "a random room"),
+        }
+    }
+}
+
+/// Return `text` with the bytes outside of the URL unreserved characters percent-encoded
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn room_names_are_validated() {
+        assert_eq!(
+            Room::named(" pong-night "),
+            Ok(Room::Named("PONG-NIGHT".to_owned()))
+        );
+        assert_eq!(Room::named("  "), Err(RoomNameError::Empty));
+        assert_eq!(Room::named("a/b"), Err(RoomNameError::NotAllowed('/')));
+        let long = "a".repeat(ROOM_NAME_MAX_LENGTH + 1);
+        assert_eq!(Room::named(&long), Err(RoomNameError::TooLong));
+        let lengthened = "ß".repeat(ROOM_NAME_MAX_LENGTH / 2 + 1);
+        assert_eq!(Room::named(&lengthened), Err(RoomNameError::TooLong));
+    }
+
+    #[test]
+    fn private_codes_differ_by_seed() {
+        let codes: Vec<Room> = (0..8).map(Room::private).collect();
+        for (i, code) in codes.iter().enumerate() {
+            assert!(!codes[..i].contains(code));
+            let Room::Named(name) = code else {
+                unreachable!()
+            };
+            assert!(name
+                .bytes()
+                .all(|c| c == b'-' || ROOM_CODE_ALPHABET.contains(&c)));
+        }
+    }
+
+    #[test]
+    fn urls_only_have_unreserved_characters() {
+        assert_eq!(percent_encode("a-Z_9.~"), "a-Z_9.~");
+        assert_eq!(percent_encode("?&/ "), "%3F%26%2F%20");
+        assert_eq!(percent_encode("ñ"), "%C3%B1");
+    }
+}
@@ -0,0 +1,166 @@
+use macroquad::{logging::warn, window::set_fullscreen};
+
+use crate::{
+    render::MacroquadBackend, session_settings::SessionSettings, sound::GameSound, theme::Theme,
+    SETTINGS_PATH,
+};
+
+/// What every screen shares: the settings, the loaded assets and the render backend
+pub struct Context {
+    pub settings: SessionSettings,
+    /// Theme loaded from [`SessionSettings::theme`]
+    pub theme: Theme,
+    /// Sounds played at [`SessionSettings::volume`]
+    pub sounds: Vec<GameSound>,
+    pub backend: MacroquadBackend,
+}
+
+impl Context {
+    /// Apply `change` to the settings of the game and to the ones saved in [`SETTINGS_PATH`].
+    /// The saved settings are read again, so that values only given on the command line are not saved,
+    /// and an invalid file is left as is rather than replaced by the defaults.
+    pub fn change_settings(&mut self, change: impl Fn(&mut SessionSettings)) {
+        let previous = self.settings.clone();
+        change(&mut self.settings);
+        match SessionSettings::read(SETTINGS_PATH) {
+            Ok(saved) => {
+                let mut saved = saved.unwrap_or_default();
+                change(&mut saved);
+                saved.save(SETTINGS_PATH);
+            }
+            Err(e) => warn!("Not saving the settings, {SETTINGS_PATH} is invalid: {e}"),
+        }
+
+        if self.settings.theme != previous.theme {
+            self.theme = Theme::load(&self.settings.theme);
+        }
+        if self.settings.fullscreen != previous.fullscreen {
+            set_fullscreen(self.settings.fullscreen);
+        }
+        for sound in &mut self.sounds {
+            sound.set_volume(self.settings.volume);
+        }
+    }
+}
+
+/// Where to go once a screen was updated
+pub enum Transition {
+    /// Stay on the current screen
+    None,
+    /// Open a screen over the current one, which is shown again once the new one is popped
+    Push(Box<dyn Screen>),
+    /// Close the current screen and open another one in its place
+    Replace(Box<dyn Screen>),
+    /// Close the current screen and go back to the one below
+    Pop,
+}
+
+/// Part of the game shown on its own, like a menu or a match
+pub trait Screen {
+    /// Handle the inputs of the frame, and return where to go next
+    fn update(&mut self, ctx: &mut Context) -> Transition;
+
+    /// Draw the screen, in window coordinates
+    fn render(&mut self, ctx: &Context);
+
+    /// Keep running while another screen is open over this one, like a match that must keep its session alive
+    fn update_below(&mut self, _ctx: &mut Context) {}
+}
+
+/// Screens opened on top of each other, only the top one is updated and drawn.
+/// The first screen is never closed.
+pub struct ScreenStack {
+    screens: Vec<Box<dyn Screen>>,
+}
+
+impl ScreenStack {
+    pub fn new(root: Box<dyn Screen>) -> Self {
+        Self {
+            screens: vec![root],
+        }
+    }
+
+    /// Amount of open screens, the first one included
+    pub fn len(&self) -> usize {
+        self.screens.len()
+    }
+
+    /// Return true if no screen is open
+    pub fn is_empty(&self) -> bool {
+        self.screens.is_empty()
+    }
+
+    /// Open `screen` over the current one
+    pub fn push(&mut self, screen: Box<dyn Screen>) {
+        self.screens.push(screen);
+    }
+
+    /// Go where `transition` leads
+    pub fn apply(&mut self, transition: Transition) {
+        match transition {
+            Transition::None => {}
+            Transition::Push(screen) => self.screens.push(screen),
+            Transition::Replace(screen) => {
+                self.screens.pop();
+                self.screens.push(screen);
+            }
+            Transition::Pop => {
+                if self.screens.len() > 1 {
+                    self.screens.pop();
+                }
+            }
+        }
+    }
+
+    /// Update the current screen, then draw the screen its transition leads to
+    pub fn run_frame(&mut self, ctx: &mut Context) {
+        let below = self.screens.len() - 1;
+        for screen in &mut self.screens[..below] {
+            screen.update_below(ctx);
+        }
+        let transition = self.top().update(ctx);
+        self.apply(transition);
+        self.top().render(ctx);
+    }
+
+    fn top(&mut self) -> &mut dyn Screen {
+        self.screens
+            .last_mut()
+            .expect("The first screen is never closed.")
+            .as_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Blank;
+
+    impl Screen for Blank {
+        fn update(&mut self, _ctx: &mut Context) -> Transition {
+            Transition::None
+        }
+
+        fn render(&mut self, _ctx: &Context) {}
+    }
+
+    #[test]
+    fn push_replace_and_pop() {
+        let mut screens = ScreenStack::new(Box::new(Blank));
+        screens.apply(Transition::Push(Box::new(Blank)));
+        assert_eq!(screens.len(), 2);
+        screens.apply(Transition::Replace(Box::new(Blank)));
+        assert_eq!(screens.len(), 2);
+        screens.apply(Transition::Pop);
+        assert_eq!(screens.len(), 1);
+    }
+
+    #[test]
+    fn first_screen_stays_open() {
+        let mut screens = ScreenStack::new(Box::new(Blank));
+        screens.apply(Transition::Pop);
+        assert_eq!(screens.len(), 1);
+        assert!(!screens.is_empty());
+    }
+}
@@ -0,0 +1,320 @@
+use std::{fs, io, mem};
+
+use macroquad::logging::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    key_bindings::KeyBindings, DEFAULT_FPS, DEFAULT_INPUT_DELAY, DEFAULT_MAX_PREDICTION_WINDOW,
+    DEFAULT_PLAYER_NAME, DEFAULT_THEME, DEFAULT_VOLUME, MATCHBOX_ADDR, MAX_AUTO_INPUT_DELAY,
+    PLAYER_NAME_MAX_LENGTH,
+};
+
+/// How the input delay of a GGRS session is chosen
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputDelay {
+    /// Pick the delay from the ping measured before the match starts
+    Auto,
+    /// Always use the given amount of frames
+    Fixed(usize),
+}
+
+impl InputDelay {
+    /// Parse an input delay from a command-line value, either `auto` or a number of frames
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::session_settings::InputDelay;
+    /// assert_eq!(InputDelay::parse("auto"), Some(InputDelay::Auto));
+    /// assert_eq!(InputDelay::parse("3"), Some(InputDelay::Fixed(3)));
+    /// assert_eq!(InputDelay::parse("fast"), None);
+    /// ```
+    pub fn parse(value: &str) -> Option<Self> {
+        if value.eq_ignore_ascii_case("auto") {
+            Some(InputDelay::Auto)
+        } else {
+            value.parse().ok().map(InputDelay::Fixed)
+        }
+    }
+
+    /// Return the amount of frames covering half of the round trip time, up to [`MAX_AUTO_INPUT_DELAY`]
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::{MAX_AUTO_INPUT_DELAY, session_settings::InputDelay};
+    /// assert_eq!(InputDelay::from_ping(0, 60), 0);
+    /// assert_eq!(InputDelay::from_ping(40, 60), 2);
+    /// assert_eq!(InputDelay::from_ping(5000, 60), MAX_AUTO_INPUT_DELAY);
+    /// ```
+    pub fn from_ping(ping_ms: u128, fps: usize) -> usize {
+        let one_way_ms = ping_ms / 2;
+        let frame_ms = (1000 / fps.max(1) as u128).max(1);
+        let frames = one_way_ms.div_ceil(frame_ms);
+        (frames as usize).min(MAX_AUTO_INPUT_DELAY)
+    }
+}
+
+/// Parameters used to build the GGRS session of a match
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionSettings {
+    /// Maximum amount of frames predicted ahead of the confirmed inputs
+    pub max_prediction_window: usize,
+    /// Frames per second the simulation runs at
+    pub fps: usize,
+    /// Input delay in frames, or automatic
+    pub input_delay: InputDelay,
+    /// Directory the replays of the matches are written to, if recording is enabled
+    pub replay_dir: Option<String>,
+    /// If true, the ball trail, paddle flashes, screen shake and particles are drawn
+    pub effects: bool,
+    /// Name of a built-in theme, or path to a theme file
+    pub theme: String,
+    /// URL of the matchbox server the rooms are on
+    pub server: String,
+    /// Volume of the sounds, in percent
+    pub volume: u8,
+    /// If true, the window starts fullscreen
+    pub fullscreen: bool,
+    /// Display name shown to the other peers
+    pub player_name: String,
+    pub key_bindings: KeyBindings,
+    /// Fields this version does not know, written by a newer one, kept so that saving does not lose them
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
+}
+
+impl SessionSettings {
+    /// Load the settings from a JSON file.
+    /// Missing fields are filled with defaults, and a missing or invalid file results in the default settings.
+    pub fn load(path: &str) -> Self {
+        Self::read(path)
+            .unwrap_or_else(|e| {
+                warn!("Invalid settings file {path}: {e}");
+                None
+            })
+            .unwrap_or_default()
+    }
+
+    /// Read the settings from a JSON file, filling the missing fields with defaults.
+    /// Return `None` if there is no such file, and why the file could not be read if it is invalid.
+    pub fn read(path: &str) -> Result<Option<Self>, String> {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str::<Self>(&contents)
+                .map(|settings| Some(settings.validated()))
+                .map_err(|e| e.to_string()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Replace the values GGRS would refuse with their defaults
+    fn validated(mut self) -> Self {
+        if self.fps == 0 {
+            warn!("The simulation cannot run at 0 fps, using {DEFAULT_FPS} fps");
+            self.fps = DEFAULT_FPS;
+        }
+        self
+    }
+
+    /// Restore every setting to its default, keeping the fields of newer versions
+    pub fn reset(&mut self) {
+        *self = Self {
+            unknown_fields: mem::take(&mut self.unknown_fields),
+            ..Self::default()
+        };
+    }
+
+    /// Display name the player joins with, [`DEFAULT_PLAYER_NAME`] if left empty
+    pub fn display_name(&self) -> String {
+        match self.player_name.trim() {
+            "" => DEFAULT_PLAYER_NAME.to_owned(),
+            name => name.chars().take(PLAYER_NAME_MAX_LENGTH).collect(),
+        }
+    }
+
+    /// Save the settings to a JSON file, logging why they could not be
+    pub fn save(&self, path: &str) {
+        let saved = serde_json::to_string_pretty(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(path, json).map_err(|e| e.to_string()));
+        if let Err(e) = saved {
+            warn!("Could not save the settings to {path}: {e}");
+        }
+    }
+
+    /// Override the settings with the values passed as command-line arguments.
+    /// Supports `--max-prediction-window <frames>`, `--fps <fps>`, `--input-delay <frames|auto>`,
+    /// `--record <dir>` and `--theme <name|path>`, other arguments are ignored.
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::session_settings::{InputDelay, SessionSettings};
+    /// let args = ["--fps", "30", "--input-delay", "auto"].map(String::from);
+    /// let settings = SessionSettings::default().with_args(args);
+    /// assert_eq!(settings.fps, 30);
+    /// assert_eq!(settings.input_delay, InputDelay::Auto);
+    /// ```
+    pub fn with_args(mut self, args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let value = match arg.as_str() {
+                "--max-prediction-window" | "--fps" | "--input-delay" | "--record" | "--theme" => {
+                    args.next()
+                }
+                _ => continue,
+            };
+            let parsed = value.and_then(|value| match arg.as_str() {
+                "--max-prediction-window" => value
+                    .parse()
+                    .ok()
+                    .map(|frames| self.max_prediction_window = frames),
+                "--fps" => value
+                    .parse()
+                    .ok()
+                    .filter(|fps| *fps > 0)
+                    .map(|fps| self.fps = fps),
+                "--record" => {
+                    self.replay_dir = Some(value);
+                    Some(())
+                }
+                "--theme" => {
+                    self.theme = value;
+                    Some(())
+                }
+                _ => InputDelay::parse(&value).map(|delay| self.input_delay = delay),
+            });
+            if parsed.is_none() {
+                warn!("Invalid value for {arg}, keeping the previous one");
+            }
+        }
+        self
+    }
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            max_prediction_window: DEFAULT_MAX_PREDICTION_WINDOW,
+            fps: DEFAULT_FPS,
+            input_delay: InputDelay::Fixed(DEFAULT_INPUT_DELAY),
+            replay_dir: None,
+            effects: true,
+            theme: DEFAULT_THEME.to_owned(),
+            server: MATCHBOX_ADDR.to_owned(),
+            volume: DEFAULT_VOLUME,
+            fullscreen: false,
+            player_name: DEFAULT_PLAYER_NAME.to_owned(),
+            key_bindings: KeyBindings::default(),
+            unknown_fields: Map::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_file_uses_defaults() {
+        let settings: SessionSettings = serde_json::from_str(r#"{"fps": 30}"#).unwrap();
+        assert_eq!(settings.fps, 30);
+        assert_eq!(
+            settings.max_prediction_window,
+            DEFAULT_MAX_PREDICTION_WINDOW
+        );
+        assert_eq!(settings.input_delay, InputDelay::Fixed(DEFAULT_INPUT_DELAY));
+    }
+
+    #[test]
+    fn auto_input_delay_from_file() {
+        let settings: SessionSettings = serde_json::from_str(r#"{"input_delay": "auto"}"#).unwrap();
+        assert_eq!(settings.input_delay, InputDelay::Auto);
+    }
+
+    #[test]
+    fn fixed_input_delay_from_file() {
+        let settings: SessionSettings =
+            serde_json::from_str(r#"{"input_delay": {"fixed": 4}}"#).unwrap();
+        assert_eq!(settings.input_delay, InputDelay::Fixed(4));
+    }
+
+    #[test]
+    fn invalid_arg_keeps_previous_value() {
+        let args = ["--fps", "many", "--max-prediction-window", "8"].map(String::from);
+        let settings = SessionSettings::default().with_args(args);
+        assert_eq!(settings.fps, DEFAULT_FPS);
+        assert_eq!(settings.max_prediction_window, 8);
+    }
+
+    #[test]
+    fn zero_fps_is_refused() {
+        let args = ["--fps", "0"].map(String::from);
+        let settings = SessionSettings::default().with_args(args);
+        assert_eq!(settings.fps, DEFAULT_FPS);
+
+        let settings: SessionSettings = serde_json::from_str(r#"{"fps": 0}"#).unwrap();
+        assert_eq!(settings.validated().fps, DEFAULT_FPS);
+    }
+
+    #[test]
+    fn record_arg_enables_replays() {
+        let args = ["--record", "replays"].map(String::from);
+        let settings = SessionSettings::default().with_args(args);
+        assert_eq!(settings.replay_dir.as_deref(), Some("replays"));
+    }
+
+    #[test]
+    fn effects_can_be_disabled_from_file() {
+        let settings: SessionSettings = serde_json::from_str(r#"{"effects": false}"#).unwrap();
+        assert!(!settings.effects);
+        assert!(SessionSettings::default().effects);
+    }
+
+    #[test]
+    fn theme_arg_overrides_file() {
+        let settings: SessionSettings = serde_json::from_str(r#"{"theme": "light"}"#).unwrap();
+        assert_eq!(settings.theme, "light");
+        let args = ["--theme", "themes/mine.json"].map(String::from);
+        let settings = settings.with_args(args);
+        assert_eq!(settings.theme, "themes/mine.json");
+    }
+
+    #[test]
+    fn fields_of_newer_versions_are_kept() {
+        let json =
+            r#"{"volume": 40, "key_bindings": {"rematch": "Space"}, "haptics": {"strength": 2}}"#;
+        let settings: SessionSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.volume, 40);
+        assert_eq!(settings.key_bindings.rematch, "Space");
+        assert_eq!(settings.key_bindings.left_paddle_up, "W");
+
+        let saved = serde_json::to_string(&settings).unwrap();
+        let reloaded: SessionSettings = serde_json::from_str(&saved).unwrap();
+        assert_eq!(reloaded, settings);
+        assert!(saved.contains(r#""haptics":{"strength":2}"#));
+    }
+
+    #[test]
+    fn reset_keeps_fields_of_newer_versions() {
+        let json = r#"{"volume": 40, "haptics": true}"#;
+        let mut settings: SessionSettings = serde_json::from_str(json).unwrap();
+        settings.reset();
+        assert_eq!(settings.volume, DEFAULT_VOLUME);
+        assert_eq!(settings.unknown_fields["haptics"], Value::Bool(true));
+    }
+
+    #[test]
+    fn missing_file_uses_defaults() {
+        let settings = SessionSettings::load("does-not-exist.json");
+        assert_eq!(settings, SessionSettings::default());
+        assert_eq!(SessionSettings::read("does-not-exist.json"), Ok(None));
+    }
+
+    #[test]
+    fn invalid_file_is_reported() {
+        let path = std::env::temp_dir().join("good-game-rusty-pong-invalid-settings.json");
+        fs::write(&path, "{ not json").unwrap();
+        let read = SessionSettings::read(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert!(read.is_err());
+    }
+}
@@ -0,0 +1,311 @@
+use std::iter;
+
+use macroquad::prelude::*;
+
+use crate::{
+    key_bindings::KeyAction,
+    lobby::is_name_char,
+    render::CommandList,
+    screen::{Context, Screen, Transition},
+    session_settings::{InputDelay, SessionSettings},
+    text_input::TextInput,
+    theme::Theme,
+    MAX_AUTO_INPUT_DELAY, PLAYER_NAME_MAX_LENGTH, SERVER_URL_MAX_LENGTH,
+};
+
+/// Setting changed from the settings screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SettingsEntry {
+    Volume,
+    Effects,
+    Theme,
+    Fullscreen,
+    Name,
+    Server,
+    InputDelay,
+    Key(KeyAction),
+    Reset,
+    Back,
+}
+
+/// Setting being typed or bound, which is only saved once confirmed
+enum Editing {
+    Text(TextInput),
+    Key(KeyAction),
+}
+
+/// Screen changing the preferences saved in the settings file
+#[derive(Default)]
+pub struct SettingsMenu {
+    /// Index of the highlighted entry in [`SettingsMenu::ENTRIES`]
+    selected: usize,
+    editing: Option<Editing>,
+    /// Why the last change was refused
+    message: Option<String>,
+}
+
+impl SettingsMenu {
+    const ENTRIES: [SettingsEntry; 15] = [
+        SettingsEntry::Volume,
+        SettingsEntry::Effects,
+        SettingsEntry::Theme,
+        SettingsEntry::Fullscreen,
+        SettingsEntry::Name,
+        SettingsEntry::Server,
+        SettingsEntry::InputDelay,
+        SettingsEntry::Key(KeyAction::LeftPaddleUp),
+        SettingsEntry::Key(KeyAction::LeftPaddleDown),
+        SettingsEntry::Key(KeyAction::RightPaddleUp),
+        SettingsEntry::Key(KeyAction::RightPaddleDown),
+        SettingsEntry::Key(KeyAction::Rematch),
+        SettingsEntry::Key(KeyAction::Pause),
+        SettingsEntry::Reset,
+        SettingsEntry::Back,
+    ];
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the settings screen in window coordinates
+    pub fn draw(&self, commands: &mut CommandList, settings: &SessionSettings) {
+        commands.text("Settings", 20.0, 50.0, 30, WHITE);
+        for (i, entry) in Self::ENTRIES.iter().enumerate() {
+            let edited = match &self.editing {
+                Some(editing) if i == self.selected => Some(editing),
+                _ => None,
+            };
+            let on_off = |on| if on { "on" } else { "off" };
+            let label = match (entry, edited) {
+                (SettingsEntry::Volume, _) => format!("Volume: {}%", settings.volume),
+                (SettingsEntry::Effects, _) => format!("Effects: {}", on_off(settings.effects)),
+                (SettingsEntry::Theme, _) => format!("Theme: {}", settings.theme),
+                (SettingsEntry::Fullscreen, _) => {
+                    format!("Fullscreen: {}", on_off(settings.fullscreen))
+                }
+                (SettingsEntry::Name, Some(Editing::Text(input))) => {
+                    format!("Name: {}", input.display(true))
+                }
+                (SettingsEntry::Name, _) => format!("Name: {}", settings.player_name),
+                (SettingsEntry::Server, Some(Editing::Text(input))) => {
+                    format!("Server: {}", input.display(true))
+                }
+                (SettingsEntry::Server, _) => format!("Server: {}", settings.server),
+                (SettingsEntry::InputDelay, _) => match settings.input_delay {
+                    InputDelay::Auto => "Input delay: auto".to_owned(),
+                    InputDelay::Fixed(frames) => format!("Input delay: {frames} frames"),
+                },
+                (SettingsEntry::Key(action), Some(Editing::Key(_))) => {
+                    format!("{}: press a key...", action.label())
+                }
+                (SettingsEntry::Key(action), _) => {
+                    format!(
+                        "{}: {}",
+                        action.label(),
+                        settings.key_bindings.name(*action)
+                    )
+                }
+                (SettingsEntry::Reset, _) => "Reset to defaults".to_owned(),
+                (SettingsEntry::Back, _) => "Back".to_owned(),
+            };
+            let (label, color) = if i == self.selected {
+                (format!("> {label}"), WHITE)
+            } else {
+                (format!("  {label}"), GRAY)
+            };
+            commands.text(&label, 20.0, 90.0 + 32.0 * i as f32, 26, color);
+        }
+
+        let bottom = 90.0 + 32.0 * Self::ENTRIES.len() as f32;
+        let hint = match self.editing {
+            Some(Editing::Text(_)) => "Type, then ENTER to save or ESC to cancel",
+            Some(Editing::Key(_)) => "Press the new key, or ESC to cancel",
+            None => "UP/DOWN to choose, LEFT/RIGHT or ENTER to change, ESC to go back",
+        };
+        commands.text(hint, 20.0, bottom + 20.0, 24, WHITE);
+        if let Some(message) = &self.message {
+            commands.text(message, 20.0, bottom + 56.0, 24, RED);
+        }
+    }
+
+    /// Handle the keys while a setting is typed or bound
+    fn update_editing(&mut self, ctx: &mut Context) {
+        // characters are queued by macroquad, they are drained even when no text is typed
+        let typed: Vec<char> = iter::from_fn(get_char_pressed).collect();
+        let Some(editing) = &mut self.editing else {
+            return;
+        };
+        if is_key_pressed(KeyCode::Escape) {
+            self.editing = None;
+            return;
+        }
+
+        match editing {
+            Editing::Text(input) => {
+                if let Err(e) = input.handle_keys(&typed) {
+                    self.message = Some(format!("Not typed: {e}"));
+                }
+                if !is_key_pressed(KeyCode::Enter) {
+                    return;
+                }
+                let text = input.text().trim().to_owned();
+                match Self::ENTRIES[self.selected] {
+                    SettingsEntry::Name => {
+                        ctx.change_settings(|settings| settings.player_name = text.clone())
+                    }
+                    SettingsEntry::Server if text.is_empty() => {
+                        self.message = Some("The server URL is empty".to_owned());
+                        return;
+                    }
+                    _ => ctx.change_settings(|settings| settings.server = text.clone()),
+                }
+            }
+            Editing::Key(action) => {
+                let Some(key) = get_last_key_pressed() else {
+                    return;
+                };
+                let action = *action;
+                let mut bindings = ctx.settings.key_bindings.clone();
+                if !bindings.bind(action, key) {
+                    self.message = Some(match bindings.action_of(key) {
+                        Some(bound) => format!("{key:?} is already bound to {}", bound.label()),
+                        None => format!("{key:?} cannot be bound"),
+                    });
+                    return;
+                }
+                ctx.change_settings(|settings| settings.key_bindings = bindings.clone());
+            }
+        }
+        self.message = None;
+        self.editing = None;
+    }
+
+    /// Change the selected setting, in the direction of `step` for the settings having a range of values
+    fn change(&mut self, ctx: &mut Context, step: isize) -> Transition {
+        let settings = &ctx.settings;
+        match Self::ENTRIES[self.selected] {
+            SettingsEntry::Volume => {
+                let volume = (settings.volume as isize + 10 * step).clamp(0, 100) as u8;
+                ctx.change_settings(|settings| settings.volume = volume);
+            }
+            SettingsEntry::Effects => {
+                let effects = !settings.effects;
+                ctx.change_settings(|settings| settings.effects = effects);
+            }
+            SettingsEntry::Theme => {
+                let theme = next_theme(&settings.theme);
+                ctx.change_settings(|settings| settings.theme = theme.to_owned());
+            }
+            SettingsEntry::Fullscreen => {
+                let fullscreen = !settings.fullscreen;
+                ctx.change_settings(|settings| settings.fullscreen = fullscreen);
+            }
+            SettingsEntry::Name => {
+                let input =
+                    TextInput::new(&settings.player_name, PLAYER_NAME_MAX_LENGTH, is_name_char);
+                self.editing = Some(Editing::Text(input));
+            }
+            SettingsEntry::Server => {
+                let input = TextInput::new(&settings.server, SERVER_URL_MAX_LENGTH, |c| {
+                    c.is_ascii_graphic()
+                });
+                self.editing = Some(Editing::Text(input));
+            }
+            SettingsEntry::InputDelay => {
+                let input_delay = next_input_delay(settings.input_delay, step);
+                ctx.change_settings(|settings| settings.input_delay = input_delay);
+            }
+            SettingsEntry::Key(action) => self.editing = Some(Editing::Key(action)),
+            SettingsEntry::Reset => ctx.change_settings(SessionSettings::reset),
+            SettingsEntry::Back => return Transition::Pop,
+        }
+        Transition::None
+    }
+}
+
+/// Return the built-in theme after `theme`, or the first one if `theme` is not built in
+fn next_theme(theme: &str) -> &'static str {
+    let next = Theme::BUILTIN
+        .iter()
+        .position(|name| *name == theme)
+        .map_or(0, |i| (i + 1) % Theme::BUILTIN.len());
+    Theme::BUILTIN[next]
+}
+
+/// Return the input delay `step` choices away from `input_delay`, in the order automatic, then every fixed delay
+/// up to [`MAX_AUTO_INPUT_DELAY`] frames, wrapping around
+fn next_input_delay(input_delay: InputDelay, step: isize) -> InputDelay {
+    let choices = MAX_AUTO_INPUT_DELAY as isize + 2;
+    let index = match input_delay {
+        InputDelay::Auto => 0,
+        InputDelay::Fixed(frames) => frames.min(MAX_AUTO_INPUT_DELAY) as isize + 1,
+    };
+    match (index + step).rem_euclid(choices) {
+        0 => InputDelay::Auto,
+        next => InputDelay::Fixed(next as usize - 1),
+    }
+}
+
+impl Screen for SettingsMenu {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if self.editing.is_some() {
+            self.update_editing(ctx);
+            return Transition::None;
+        }
+        // characters typed outside of a text field are dropped, not to be typed in the next one
+        while get_char_pressed().is_some() {}
+
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1).min(Self::ENTRIES.len() - 1);
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            return Transition::Pop;
+        }
+        if is_key_pressed(KeyCode::Left) {
+            self.change(ctx, -1)
+        } else if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::Enter) {
+            self.change(ctx, 1)
+        } else {
+            Transition::None
+        }
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        let mut commands = CommandList::new();
+        self.draw(&mut commands, &ctx.settings);
+        ctx.backend.execute(&commands);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_cycle_through_builtin_ones() {
+        assert_eq!(next_theme("classic"), "light");
+        assert_eq!(
+            next_theme(Theme::BUILTIN[Theme::BUILTIN.len() - 1]),
+            "classic"
+        );
+        assert_eq!(next_theme("themes/neon.json"), "classic");
+    }
+
+    #[test]
+    fn input_delays_cycle_from_auto() {
+        assert_eq!(next_input_delay(InputDelay::Auto, 1), InputDelay::Fixed(0));
+        assert_eq!(next_input_delay(InputDelay::Fixed(0), -1), InputDelay::Auto);
+        assert_eq!(
+            next_input_delay(InputDelay::Auto, -1),
+            InputDelay::Fixed(MAX_AUTO_INPUT_DELAY)
+        );
+        assert_eq!(
+            next_input_delay(InputDelay::Fixed(MAX_AUTO_INPUT_DELAY), 1),
+            InputDelay::Auto
+        );
+    }
+}
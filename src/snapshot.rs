@@ -0,0 +1,181 @@
+//! Versioned serialization of [`GameState`].
+//!
+//! A snapshot is the [`SNAPSHOT_MAGIC`], the schema version, then the bincode encoding of the game state,
+//! whose layout follows the order of the fields. Once snapshots of a version were shared, any change to the fields
+//! of [`GameState`] or of the types it contains must bump [`SNAPSHOT_VERSION`], keep the previous layout as its own
+//! struct, and convert it into the current one in [`decode`].
+
+use std::fmt;
+
+use crate::game_state::GameState;
+
+/// First bytes of every snapshot
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"GGSS";
+/// Version of the [`GameState`] schema
+pub const SNAPSHOT_VERSION: u16 = 1;
+
+/// Error happening while encoding or decoding a snapshot
+#[derive(Debug)]
+pub enum SnapshotError {
+    Format(bincode::Error),
+    Json(serde_json::Error),
+    /// The data does not start with [`SNAPSHOT_MAGIC`]
+    NotASnapshot,
+    /// The snapshot was written with a schema version this build cannot read
+    UnsupportedVersion(u16),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Format(e) => write!(f, "invalid snapshot: {e}"),
+            SnapshotError::Json(e) => write!(f, "invalid JSON: {e}"),
+            SnapshotError::NotASnapshot => write!(f, "not a game state snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {version}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<bincode::Error> for SnapshotError {
+    fn from(e: bincode::Error) -> Self {
+        SnapshotError::Format(e)
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(e: serde_json::Error) -> Self {
+        SnapshotError::Json(e)
+    }
+}
+
+/// Encode `state` into a snapshot of the current schema version
+pub fn encode(state: &GameState) -> Result<Vec<u8>, SnapshotError> {
+    Ok(bincode::serialize(&(
+        SNAPSHOT_MAGIC,
+        SNAPSHOT_VERSION,
+        state,
+    ))?)
+}
+
+/// Decode a snapshot of any supported schema version, migrating it to the current one
+pub fn decode(bytes: &[u8]) -> Result<GameState, SnapshotError> {
+    let (magic, version): ([u8; 4], u16) = bincode::deserialize(bytes)?;
+    if magic != SNAPSHOT_MAGIC {
+        return Err(SnapshotError::NotASnapshot);
+    }
+
+    match version {
+        SNAPSHOT_VERSION => {
+            let (_, _, state): ([u8; 4], u16, GameState) = bincode::deserialize(bytes)?;
+            Ok(state)
+        }
+        version => Err(SnapshotError::UnsupportedVersion(version)),
+    }
+}
+
+/// Export `state` as human readable JSON, for debugging
+pub fn to_json(state: &GameState) -> Result<String, SnapshotError> {
+    Ok(serde_json::to_string_pretty(state)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use ggrs::NULL_FRAME;
+
+    use super::*;
+    use crate::{
+        game_state::Side, match_rules::MatchPreset, INITIAL_VELOCITY, MAX_PAUSES_PER_PLAYER,
+        NUM_PLAYERS, PADDLE_HEIGHT, PADDLE_SPEED, SCORE_MAX_VALUE,
+    };
+
+    fn played_state() -> GameState {
+        let mut state = GameState::for_match(MatchPreset::Fast.rules(), 5);
+        state.left_paddle.score = 3;
+        state.right_paddle.pos = 120;
+        state.ball.pos_x = 42;
+        state.winner = Some(Side::Right);
+        state.rematch_votes = [true, false];
+        state
+    }
+
+    #[test]
+    fn round_trip() {
+        let state = played_state();
+        let bytes = encode(&state).unwrap();
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(encode(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn schema_layout_is_stable() {
+        let mut expected = SNAPSHOT_MAGIC.to_vec();
+        expected.extend(SNAPSHOT_VERSION.to_le_bytes());
+        expected.extend(0i32.to_le_bytes()); // frame
+        for _ in 0..2 {
+            // last and periodic checksums
+            expected.extend(NULL_FRAME.to_le_bytes());
+            expected.extend(0u64.to_le_bytes());
+        }
+        expected.extend(0u64.to_le_bytes()); // sound played
+        for _ in 0..2 {
+            // paddles score, position, velocity and height
+            for value in [0, 1, 0, PADDLE_HEIGHT as i32] {
+                expected.extend(value.to_le_bytes());
+            }
+        }
+        for value in [0, 0, INITIAL_VELOCITY, INITIAL_VELOCITY] {
+            // ball position and velocity
+            expected.extend(value.to_le_bytes());
+        }
+        expected.push(0); // ball changed direction
+        expected.push(0); // no winner
+        expected.extend([0, 0]); // rematch votes
+        expected.extend(0u64.to_le_bytes()); // random source
+        for value in [
+            SCORE_MAX_VALUE,
+            INITIAL_VELOCITY,
+            PADDLE_SPEED,
+            PADDLE_HEIGHT as i32,
+        ] {
+            // match rules
+            expected.extend(value.to_le_bytes());
+        }
+        expected.push(0); // not won by two
+        expected.push(0); // no score cap
+        expected.extend(1i32.to_le_bytes()); // best of one set
+        expected.push(0); // no side switching
+        expected.extend([0u8; 8]); // sets won on each side
+        expected.push(0); // sides not switched
+        expected.push(0); // not paused
+        expected.extend([MAX_PAUSES_PER_PLAYER; NUM_PLAYERS]); // pauses left
+        expected.extend([0, 0]); // resume votes
+
+        assert_eq!(encode(&GameState::new()).unwrap(), expected);
+    }
+
+    #[test]
+    fn reject_unknown_data() {
+        assert!(matches!(
+            decode(b"definitely not a snapshot"),
+            Err(SnapshotError::NotASnapshot)
+        ));
+        let bytes = bincode::serialize(&(SNAPSHOT_MAGIC, SNAPSHOT_VERSION + 1)).unwrap();
+        assert!(matches!(
+            decode(&bytes),
+            Err(SnapshotError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn json_export() {
+        let json = to_json(&played_state()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["left_paddle"]["score"], 3);
+        assert_eq!(value["ball"]["pos_x"], 42);
+        assert_eq!(value["winner"], "Right");
+    }
+}
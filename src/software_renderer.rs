@@ -0,0 +1,258 @@
+use std::{fs, io, path::Path};
+
+use fontdue::{Font, FontSettings};
+use macroquad::{color::Color, math::Rect};
+
+use crate::render::{CommandList, DrawCommand, ImageId, TextAlign};
+
+/// Image with 8 bit RGBA pixels, row by row from the top left corner
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl RgbaImage {
+    /// Decode a PNG image, whatever its color type and bit depth
+    pub fn decode_png(bytes: &[u8]) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+
+        let pixels = buffer[..info.buffer_size()]
+            .chunks(info.color_type.samples())
+            .map(|pixel| match *pixel {
+                [gray] => [gray, gray, gray, 255],
+                [gray, alpha] => [gray, gray, gray, alpha],
+                [r, g, b] => [r, g, b, 255],
+                [r, g, b, a] => [r, g, b, a],
+                _ => unreachable!("PNG pixels have 1 to 4 samples."),
+            })
+            .collect();
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    pub fn encode_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        let data: Vec<u8> = self.pixels.iter().flatten().copied().collect();
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(bytes)
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+/// Executes command lists into an in-memory image, without a window or a GPU, for screenshot tests.
+/// Texts are rasterized with the game font, so they look close to, but not exactly like, the macroquad ones.
+pub struct SoftwareRenderer {
+    image: RgbaImage,
+    font: Font,
+    logo: RgbaImage,
+}
+
+impl SoftwareRenderer {
+    /// Return a renderer drawing to a black `width` by `height` image
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::{render::CommandList, software_renderer::SoftwareRenderer};
+    /// # use macroquad::color::WHITE;
+    /// let mut commands = CommandList::new();
+    /// commands.rectangle(2.0, 2.0, 4.0, 4.0, WHITE);
+    /// let mut renderer = SoftwareRenderer::new(8, 8);
+    /// renderer.execute(&commands);
+    /// assert_eq!(renderer.image().pixel(0, 0), [0, 0, 0, 255]);
+    /// assert_eq!(renderer.image().pixel(3, 3), [255, 255, 255, 255]);
+    /// ```
+    pub fn new(width: u32, height: u32) -> Self {
+        let font = Font::from_bytes(
+            include_bytes!("../assets/FiraSans-Regular.ttf") as &[u8],
+            FontSettings::default(),
+        )
+        .expect("The game font is valid.");
+        let logo = RgbaImage::decode_png(include_bytes!("../assets/logo.png"))
+            .expect("The logo is a valid PNG.");
+        Self {
+            image: RgbaImage {
+                width,
+                height,
+                pixels: vec![[0, 0, 0, 255]; (width * height) as usize],
+            },
+            font,
+            logo,
+        }
+    }
+
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// Write the image drawn so far to a PNG file
+    pub fn save_png(&self, path: &Path) -> io::Result<()> {
+        let bytes = self.image.encode_png().map_err(io::Error::other)?;
+        fs::write(path, bytes)
+    }
+
+    pub fn execute(&mut self, commands: &CommandList) {
+        for command in commands.commands() {
+            match command {
+                DrawCommand::Rectangle { rect, color } => self.fill_rect(*rect, *color),
+                DrawCommand::Text {
+                    text,
+                    x,
+                    y,
+                    font_size,
+                    align,
+                    color,
+                } => {
+                    let x = match align {
+                        TextAlign::Left => *x,
+                        TextAlign::Center => x - self.text_width(text, *font_size) / 2.0,
+                    };
+                    self.draw_text(text, x, *y, *font_size, *color);
+                }
+                DrawCommand::Image { image, rect } => match image {
+                    ImageId::Logo => self.draw_logo(*rect),
+                },
+            }
+        }
+    }
+
+    /// Return the range of pixels covered by `start..start + length`, clipped to `0..max`
+    fn span(start: f32, length: f32, max: u32) -> std::ops::Range<u32> {
+        let first = start.round().clamp(0.0, max as f32) as u32;
+        let last = (start + length).round().clamp(0.0, max as f32) as u32;
+        first..last
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        for y in Self::span(rect.y, rect.h, self.image.height) {
+            for x in Self::span(rect.x, rect.w, self.image.width) {
+                self.blend(x, y, color, 1.0);
+            }
+        }
+    }
+
+    fn text_width(&self, text: &str, font_size: u16) -> f32 {
+        text.chars()
+            .map(|c| self.font.metrics(c, font_size as f32).advance_width)
+            .sum()
+    }
+
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: u16, color: Color) {
+        let mut pen_x = x;
+        for c in text.chars() {
+            let (metrics, coverage) = self.font.rasterize(c, font_size as f32);
+            let left = pen_x.round() as i32 + metrics.xmin;
+            let top = y.round() as i32 - metrics.ymin - metrics.height as i32;
+            for row in 0..metrics.height {
+                for column in 0..metrics.width {
+                    let (px, py) = (left + column as i32, top + row as i32);
+                    if px < 0
+                        || py < 0
+                        || px >= self.image.width as i32
+                        || py >= self.image.height as i32
+                    {
+                        continue;
+                    }
+                    let alpha = coverage[row * metrics.width + column] as f32 / 255.0;
+                    self.blend(px as u32, py as u32, color, alpha);
+                }
+            }
+            pen_x += metrics.advance_width;
+        }
+    }
+
+    /// Draw the logo scaled to `rect`, sampling the nearest pixel
+    fn draw_logo(&mut self, rect: Rect) {
+        let ys = Self::span(rect.y, rect.h, self.image.height);
+        let xs = Self::span(rect.x, rect.w, self.image.width);
+        for y in ys {
+            for x in xs.clone() {
+                let u = ((x as f32 + 0.5 - rect.x) / rect.w * self.logo.width as f32) as u32;
+                let v = ((y as f32 + 0.5 - rect.y) / rect.h * self.logo.height as f32) as u32;
+                let [r, g, b, a] = self
+                    .logo
+                    .pixel(u.min(self.logo.width - 1), v.min(self.logo.height - 1));
+                let color = Color::from_rgba(r, g, b, 255);
+                self.blend(x, y, color, a as f32 / 255.0);
+            }
+        }
+    }
+
+    /// Draw `color` over the pixel at (x, y), with `coverage` of its alpha
+    fn blend(&mut self, x: u32, y: u32, color: Color, coverage: f32) {
+        let alpha = color.a * coverage;
+        let index = (y * self.image.width + x) as usize;
+        let pixel = &mut self.image.pixels[index];
+        for (channel, value) in pixel.iter_mut().zip([color.r, color.g, color.b]) {
+            let blended = value * 255.0 * alpha + *channel as f32 * (1.0 - alpha);
+            *channel = blended.round() as u8;
+        }
+        let blended_alpha = alpha * 255.0 + pixel[3] as f32 * (1.0 - alpha);
+        pixel[3] = blended_alpha.round() as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use macroquad::color::WHITE;
+
+    #[test]
+    fn rectangles_are_clipped() {
+        let mut commands = CommandList::new();
+        commands.rectangle(-5.0, -5.0, 7.0, 100.0, WHITE);
+        let mut renderer = SoftwareRenderer::new(4, 4);
+        renderer.execute(&commands);
+        let image = renderer.image();
+        assert_eq!(image.pixel(1, 3), [255, 255, 255, 255]);
+        assert_eq!(image.pixel(2, 0), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn translucent_colors_are_blended() {
+        let mut commands = CommandList::new();
+        commands.rectangle(0.0, 0.0, 1.0, 1.0, Color::new(1.0, 1.0, 1.0, 0.5));
+        let mut renderer = SoftwareRenderer::new(1, 1);
+        renderer.execute(&commands);
+        assert_eq!(renderer.image().pixel(0, 0), [128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn text_is_drawn_above_its_baseline() {
+        let mut commands = CommandList::new();
+        commands.text("I", 2.0, 20.0, 16, WHITE);
+        let mut renderer = SoftwareRenderer::new(16, 24);
+        renderer.execute(&commands);
+        let image = renderer.image();
+        let lit_rows: Vec<u32> = (0..image.height)
+            .filter(|y| (0..image.width).any(|x| image.pixel(x, *y)[0] > 0))
+            .collect();
+        assert!(!lit_rows.is_empty());
+        assert!(lit_rows.iter().all(|y| *y < 20));
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let mut commands = CommandList::new();
+        commands.rectangle(1.0, 0.0, 1.0, 2.0, Color::from_rgba(10, 20, 30, 255));
+        let mut renderer = SoftwareRenderer::new(3, 2);
+        renderer.execute(&commands);
+        let bytes = renderer.image().encode_png().unwrap();
+        assert_eq!(&RgbaImage::decode_png(&bytes).unwrap(), renderer.image());
+    }
+}
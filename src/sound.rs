@@ -0,0 +1,34 @@
+use macroquad::audio::{play_sound, PlaySoundParams, Sound};
+
+/// Sound effect of the game, with the volume of the settings
+#[derive(Clone, Copy)]
+pub struct GameSound {
+    pub sound: Sound,
+    /// Volume from 0, muted, to 1
+    pub volume: f32,
+}
+
+impl GameSound {
+    pub fn new(sound: Sound, volume_percent: u8) -> Self {
+        let mut game_sound = Self { sound, volume: 1.0 };
+        game_sound.set_volume(volume_percent);
+        game_sound
+    }
+
+    /// Set the volume from a percentage, as saved in the settings
+    pub fn set_volume(&mut self, volume_percent: u8) {
+        self.volume = volume_percent.min(100) as f32 / 100.0;
+    }
+
+    pub fn play(&self) {
+        if self.volume > 0.0 {
+            play_sound(
+                self.sound,
+                PlaySoundParams {
+                    looped: false,
+                    volume: self.volume,
+                },
+            );
+        }
+    }
+}
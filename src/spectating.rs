@@ -0,0 +1,100 @@
+use async_executor::LocalExecutor;
+use ggrs::{GGRSError, SessionBuilder, SessionState, SpectatorSession};
+use macroquad::prelude::*;
+use matchbox_socket::WebRtcSocket;
+
+use crate::{
+    game_state::GameState,
+    ggrs_config::GGRSConfig,
+    handshake::{Handshake, HandshakeAwareSocket},
+    match_screen::{Match, MatchDriver, MatchView},
+    session_settings::SessionSettings,
+    sound::GameSound,
+    NUM_PLAYERS, SPECTATOR_CATCHUP_SPEED, SPECTATOR_DELAY_FRAMES, SPECTATOR_MAX_FRAMES_BEHIND,
+};
+
+/// Follows the match of two remote players through a GGRS spectator session, a few frames behind its host
+pub struct SpectatorDriver {
+    session: SpectatorSession<GGRSConfig>,
+    /// Runs the message loop of the socket
+    executor: LocalExecutor<'static>,
+}
+
+impl SpectatorDriver {
+    /// Start watching the match agreed on during the `handshake`, over `socket` whose message loop runs on `executor`
+    pub fn start(
+        socket: WebRtcSocket,
+        handshake: &Handshake,
+        executor: LocalExecutor<'static>,
+        settings: &SessionSettings,
+    ) -> Match {
+        let host = handshake
+            .host_id(socket.id())
+            .expect("Should only start spectating once the players are connected.");
+        let rules = handshake
+            .match_rules(socket.id())
+            .expect("Should only start spectating once the rules are known.");
+
+        info!("Watching the match hosted by {host} with {rules:?}...");
+        let game_state = GameState::for_match(rules, handshake.match_seed(socket.id()));
+        let view = MatchView::new(
+            game_state,
+            handshake.player_names(socket.id()),
+            settings.effects,
+        );
+
+        let session = SessionBuilder::<GGRSConfig>::new()
+            .with_num_players(NUM_PLAYERS)
+            .with_fps(settings.fps)
+            .expect("Invalid FPS")
+            .with_max_frames_behind(SPECTATOR_MAX_FRAMES_BEHIND)
+            .expect("Invalid max frames behind")
+            .with_catchup_speed(SPECTATOR_CATCHUP_SPEED)
+            .expect("Invalid catchup speed")
+            .start_spectator_session(host, HandshakeAwareSocket::new(socket));
+
+        Match::new(Box::new(Self { session, executor }), view)
+    }
+}
+
+impl MatchDriver for SpectatorDriver {
+    fn poll(&mut self) {
+        self.executor.try_tick();
+        self.session.poll_remote_clients();
+        self.executor.try_tick();
+    }
+
+    fn advance(
+        &mut self,
+        state: &mut GameState,
+        _keys: u8,
+        _actions: [u8; NUM_PLAYERS],
+        sounds: &[GameSound],
+    ) -> bool {
+        // stay a few frames behind the host to smooth out network hiccups
+        if self.session.current_state() != SessionState::Running
+            || self.session.frames_behind_host() <= SPECTATOR_DELAY_FRAMES
+        {
+            return false;
+        }
+
+        match self.session.advance_frame() {
+            Ok(requests) => {
+                state.handle_requests(requests, sounds, None);
+                true
+            }
+            Err(GGRSError::PredictionThreshold) => false,
+            Err(e) => {
+                panic!("Unknown error happened during SpectatorSession::<_>::advance_frame(): {e}")
+            }
+        }
+    }
+
+    fn after_frames(&mut self) {
+        self.executor.try_tick();
+    }
+
+    fn local_handle(&self) -> Option<usize> {
+        None
+    }
+}
@@ -0,0 +1,86 @@
+use ggrs::{SessionBuilder, SyncTestSession};
+
+use crate::{
+    game_state::GameState,
+    ggrs_config::GGRSConfig,
+    input::Input,
+    local_play::{keyboard_handles, local_inputs, new_local_game, Controller},
+    match_rules::MatchRules,
+    match_screen::{Match, MatchDriver, MatchView},
+    session_settings::SessionSettings,
+    sound::GameSound,
+    NUM_PLAYERS, SYNC_TEST_CHECK_DISTANCE,
+};
+
+/// Plays a local match through a GGRS synchronization test session, which rolls back and resimulates every frame
+/// to check that the same inputs always give the same game state, panicking as soon as they do not
+pub struct SyncTestDriver {
+    session: SyncTestSession<GGRSConfig>,
+    controllers: [Controller; NUM_PLAYERS],
+}
+
+impl SyncTestDriver {
+    pub fn new(controllers: [Controller; NUM_PLAYERS], settings: &SessionSettings) -> Self {
+        let session = SessionBuilder::<GGRSConfig>::new()
+            .with_num_players(NUM_PLAYERS)
+            .with_max_prediction_window(settings.max_prediction_window)
+            .with_check_distance(SYNC_TEST_CHECK_DISTANCE)
+            .start_synctest_session()
+            .expect("Synchronization test session could not be created.");
+        Self {
+            session,
+            controllers,
+        }
+    }
+
+    /// Start a match between the `controllers` playing by `rules`
+    pub fn start(
+        controllers: [Controller; NUM_PLAYERS],
+        rules: MatchRules,
+        settings: &SessionSettings,
+    ) -> Match {
+        let view = MatchView::new(new_local_game(rules), Vec::new(), settings.effects);
+        Match::new(Box::new(Self::new(controllers, settings)), view)
+    }
+}
+
+impl MatchDriver for SyncTestDriver {
+    fn advance(
+        &mut self,
+        state: &mut GameState,
+        keys: u8,
+        actions: [u8; NUM_PLAYERS],
+        _sounds: &[GameSound],
+    ) -> bool {
+        for (handle, inp) in local_inputs(state, keys, actions, &self.controllers)
+            .into_iter()
+            .enumerate()
+        {
+            self.session
+                .add_local_input(handle, Input { inp })
+                .expect("Invalid player handle");
+        }
+
+        match self.session.advance_frame() {
+            Ok(requests) => {
+                // every frame is resimulated several times, it is played silently to not repeat its sounds
+                state.handle_requests(requests, &[], None);
+                true
+            }
+            Err(e) => panic!(
+                "Synchronization test failed at frame {}: {e}",
+                state.frame()
+            ),
+        }
+    }
+
+    fn local_handle(&self) -> Option<usize> {
+        self.controllers
+            .iter()
+            .position(|controller| *controller == Controller::Keyboard)
+    }
+
+    fn local_handles(&self) -> Vec<usize> {
+        keyboard_handles(&self.controllers)
+    }
+}
@@ -0,0 +1,254 @@
+use std::fmt;
+
+use macroquad::{prelude::*, window::get_internal_gl};
+
+/// Reason typed or pasted characters were not added to a [`TextInput`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextInputError {
+    /// The character is not allowed in the field
+    NotAllowed(char),
+    /// The field already holds its maximum amount of characters
+    TooLong(usize),
+}
+
+impl fmt::Display for TextInputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextInputError::NotAllowed(c) => write!(f, "{c:?} is not allowed"),
+            TextInputError::TooLong(max_length) => write!(f, "at most {max_length} characters"),
+        }
+    }
+}
+
+/// Single line text field: characters are typed at the cursor, which moves with `LEFT`, `RIGHT`, `HOME` and `END`,
+/// `BACKSPACE` and `DELETE` erase around it, and `CTRL+V` pastes the clipboard
+#[derive(Clone, Debug)]
+pub struct TextInput {
+    text: String,
+    /// Position of the cursor, in characters from the start of the text
+    cursor: usize,
+    max_length: usize,
+    /// Return true for the characters the field accepts
+    allowed: fn(char) -> bool,
+}
+
+impl TextInput {
+    /// Return a field holding `text`, with the cursor at its end
+    pub fn new(text: &str, max_length: usize, allowed: fn(char) -> bool) -> Self {
+        Self {
+            text: text.to_owned(),
+            cursor: text.chars().count(),
+            max_length,
+            allowed,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Replace the whole text, moving the cursor to its end
+    pub fn set_text(&mut self, text: &str) {
+        self.text.clear();
+        self.cursor = 0;
+        // the text comes from the program, not the player, there is nobody to report the rejections to
+        let _ = self.insert(text.chars());
+    }
+
+    /// Insert the `typed` characters at the cursor, skipping the ones not allowed or not fitting.
+    /// Return the first reason a character was skipped, if any.
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::text_input::{TextInput, TextInputError};
+    /// let mut input = TextInput::new("ac", 4, |c| c.is_ascii_lowercase());
+    /// input.move_cursor(-1);
+    /// assert_eq!(input.insert("B!b".chars()), Err(TextInputError::NotAllowed('B')));
+    /// assert_eq!(input.text(), "abc");
+    /// assert_eq!(input.insert("xyz".chars()), Err(TextInputError::TooLong(4)));
+    /// assert_eq!(input.text(), "abxc");
+    /// ```
+    pub fn insert(&mut self, typed: impl IntoIterator<Item = char>) -> Result<(), TextInputError> {
+        let mut result = Ok(());
+        for c in typed {
+            let rejection = if !(self.allowed)(c) {
+                Some(TextInputError::NotAllowed(c))
+            } else if self.text.chars().count() >= self.max_length {
+                Some(TextInputError::TooLong(self.max_length))
+            } else {
+                None
+            };
+            match rejection {
+                Some(error) => result = result.and(Err(error)),
+                None => {
+                    let index = self.byte_index(self.cursor);
+                    self.text.insert(index, c);
+                    self.cursor += 1;
+                }
+            }
+        }
+        result
+    }
+
+    /// Erase the character before the cursor
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let index = self.byte_index(self.cursor);
+            self.text.remove(index);
+        }
+    }
+
+    /// Erase the character after the cursor
+    pub fn delete(&mut self) {
+        if self.cursor < self.text.chars().count() {
+            let index = self.byte_index(self.cursor);
+            self.text.remove(index);
+        }
+    }
+
+    /// Move the cursor by `offset` characters, staying within the text
+    pub fn move_cursor(&mut self, offset: isize) {
+        let length = self.text.chars().count() as isize;
+        self.cursor = (self.cursor as isize + offset).clamp(0, length) as usize;
+    }
+
+    /// Handle the editing keys of the frame, `typed` being the characters queued by macroquad since the previous one.
+    /// Return the first reason a typed or pasted character was skipped, if any.
+    pub fn handle_keys(&mut self, typed: &[char]) -> Result<(), TextInputError> {
+        if is_key_pressed(KeyCode::Left) {
+            self.move_cursor(-1);
+        }
+        if is_key_pressed(KeyCode::Right) {
+            self.move_cursor(1);
+        }
+        if is_key_pressed(KeyCode::Home) {
+            self.cursor = 0;
+        }
+        if is_key_pressed(KeyCode::End) {
+            self.cursor = self.text.chars().count();
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.backspace();
+        }
+        if is_key_pressed(KeyCode::Delete) {
+            self.delete();
+        }
+
+        if !is_control_down() {
+            // control characters like the backspace one are handled with their keys above
+            return self.insert(typed.iter().copied().filter(|c| !c.is_control()));
+        }
+        if !is_key_pressed(KeyCode::V) {
+            return Ok(());
+        }
+        match clipboard_text() {
+            // pasted text often ends with a new line
+            Some(pasted) => self.insert(pasted.trim().chars()),
+            None => Ok(()),
+        }
+    }
+
+    /// Return the text with a `|` at the cursor, or the text alone if the field is not `focused`
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::text_input::TextInput;
+    /// let mut input = TextInput::new("héllo", 10, char::is_alphanumeric);
+    /// input.move_cursor(-3);
+    /// assert_eq!(input.display(true), "hé|llo");
+    /// assert_eq!(input.display(false), "héllo");
+    /// ```
+    pub fn display(&self, focused: bool) -> String {
+        let mut display = self.text.clone();
+        if focused {
+            display.insert(self.byte_index(self.cursor), '|');
+        }
+        display
+    }
+
+    /// Index in bytes of the character at position `cursor`
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+}
+
+/// Return true if a key making shortcuts like `CTRL+V` is held, `CMD` counting as `CTRL` for macOS players
+pub fn is_control_down() -> bool {
+    [
+        KeyCode::LeftControl,
+        KeyCode::RightControl,
+        KeyCode::LeftSuper,
+        KeyCode::RightSuper,
+    ]
+    .into_iter()
+    .any(is_key_down)
+}
+
+/// Return the text of the system clipboard, if any
+pub fn clipboard_text() -> Option<String> {
+    // the clipboard belongs to the window, which macroquad only exposes through its internal context
+    let gl = unsafe { get_internal_gl() };
+    gl.quad_context.clipboard_get()
+}
+
+/// Replace the text of the system clipboard with `text`
+pub fn set_clipboard_text(text: &str) {
+    let gl = unsafe { get_internal_gl() };
+    gl.quad_context.clipboard_set(text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn letters(text: &str) -> TextInput {
+        TextInput::new(text, 8, char::is_alphabetic)
+    }
+
+    #[test]
+    fn erase_around_the_cursor() {
+        let mut input = letters("abcd");
+        input.move_cursor(-2);
+        input.backspace();
+        assert_eq!(input.text(), "acd");
+        input.delete();
+        assert_eq!(input.text(), "ad");
+        assert_eq!(input.cursor(), 1);
+    }
+
+    #[test]
+    fn cursor_stays_within_the_text() {
+        let mut input = letters("ab");
+        input.move_cursor(5);
+        assert_eq!(input.cursor(), 2);
+        input.move_cursor(-5);
+        assert_eq!(input.cursor(), 0);
+        input.backspace();
+        assert_eq!(input.text(), "ab");
+    }
+
+    #[test]
+    fn multi_byte_characters() {
+        let mut input = letters("ñu");
+        input.move_cursor(-1);
+        input.insert(['ü']).unwrap();
+        assert_eq!(input.text(), "ñüu");
+        input.backspace();
+        input.backspace();
+        assert_eq!(input.text(), "u");
+    }
+
+    #[test]
+    fn set_text_drops_what_does_not_fit() {
+        let mut input = letters("");
+        input.set_text("abc1defghijk");
+        assert_eq!(input.text(), "abcdefgh");
+        assert_eq!(input.cursor(), 8);
+    }
+}
@@ -0,0 +1,188 @@
+use std::fs;
+
+use macroquad::{color::Color, logging::warn};
+use serde::{Deserialize, Serialize};
+
+/// Colors the playfield is drawn with
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    #[serde(with = "hex_color")]
+    pub background: Color,
+    #[serde(with = "hex_color")]
+    pub paddles: Color,
+    #[serde(with = "hex_color")]
+    pub ball: Color,
+    /// Dashed line in the middle of the court
+    #[serde(with = "hex_color")]
+    pub net: Color,
+    #[serde(with = "hex_color")]
+    pub text: Color,
+    /// Flash over a paddle hitting the ball, fading out when effects are enabled
+    #[serde(with = "hex_color")]
+    pub paddle_flash: Color,
+    /// If true, the top and bottom edges of the court are drawn with the color of the net
+    pub court_lines: bool,
+}
+
+impl Theme {
+    /// Names of the themes that do not need a theme file
+    pub const BUILTIN: [&'static str; 4] = ["classic", "light", "high_contrast", "colour_blind"];
+
+    /// White on black, like the original game
+    pub fn classic() -> Self {
+        Self {
+            background: Color::from_rgba(0, 0, 0, 255),
+            paddles: Color::from_rgba(255, 255, 255, 255),
+            ball: Color::from_rgba(255, 255, 255, 255),
+            net: Color::from_rgba(128, 128, 128, 255),
+            text: Color::from_rgba(255, 255, 255, 255),
+            paddle_flash: Color::from_rgba(255, 204, 51, 255),
+            court_lines: false,
+        }
+    }
+
+    /// Return the built-in theme called `name`, if any
+    /// # Examples
+    /// ```
+    /// # use good_game_rusty_pong::theme::Theme;
+    /// assert_eq!(Theme::builtin("classic"), Some(Theme::classic()));
+    /// assert!(Theme::BUILTIN.iter().all(|name| Theme::builtin(name).is_some()));
+    /// assert_eq!(Theme::builtin("neon"), None);
+    /// ```
+    pub fn builtin(name: &str) -> Option<Self> {
+        let theme = match name {
+            "classic" => Self::classic(),
+            "light" => Self {
+                background: Color::from_rgba(240, 236, 226, 255),
+                paddles: Color::from_rgba(40, 40, 40, 255),
+                ball: Color::from_rgba(40, 40, 40, 255),
+                net: Color::from_rgba(170, 165, 155, 255),
+                text: Color::from_rgba(40, 40, 40, 255),
+                paddle_flash: Color::from_rgba(214, 120, 0, 255),
+                court_lines: true,
+            },
+            // pure colors with the largest luminance differences
+            "high_contrast" => Self {
+                background: Color::from_rgba(0, 0, 0, 255),
+                paddles: Color::from_rgba(255, 255, 255, 255),
+                ball: Color::from_rgba(255, 255, 0, 255),
+                net: Color::from_rgba(255, 255, 255, 255),
+                text: Color::from_rgba(255, 255, 0, 255),
+                paddle_flash: Color::from_rgba(255, 0, 255, 255),
+                court_lines: true,
+            },
+            // Okabe-Ito palette, distinguishable with the common color vision deficiencies
+            "colour_blind" => Self {
+                background: Color::from_rgba(0, 0, 0, 255),
+                paddles: Color::from_rgba(86, 180, 233, 255),
+                ball: Color::from_rgba(230, 159, 0, 255),
+                net: Color::from_rgba(0, 114, 178, 255),
+                text: Color::from_rgba(240, 228, 66, 255),
+                paddle_flash: Color::from_rgba(213, 94, 0, 255),
+                court_lines: false,
+            },
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    /// Return the built-in theme called `name`, or load the theme file at path `name`.
+    /// Missing colors are taken from the classic theme, and a missing or invalid file results in the classic theme.
+    pub fn load(name: &str) -> Self {
+        if let Some(theme) = Self::builtin(name) {
+            return theme;
+        }
+
+        match fs::read_to_string(name) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Invalid theme file {name}: {e}");
+                Self::classic()
+            }),
+            Err(e) => {
+                warn!("Unknown theme {name}: {e}");
+                Self::classic()
+            }
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+/// Colors written as `#rrggbb` or `#rrggbbaa` in theme files
+mod hex_color {
+    use macroquad::color::Color;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        let [r, g, b, a] = [color.r, color.g, color.b, color.a].map(|c| (c * 255.0).round() as u8);
+        serializer.serialize_str(&format!("#{r:02x}{g:02x}{b:02x}{a:02x}"))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        parse(&hex).ok_or_else(|| D::Error::custom(format!("invalid color {hex}")))
+    }
+
+    pub(super) fn parse(hex: &str) -> Option<Color> {
+        let digits = hex.strip_prefix('#')?;
+        if !matches!(digits.len(), 6 | 8) || !digits.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+        let alpha = if digits.len() == 8 { channel(6)? } else { 255 };
+        Some(Color::from_rgba(
+            channel(0)?,
+            channel(2)?,
+            channel(4)?,
+            alpha,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_colors() {
+        assert_eq!(
+            hex_color::parse("#ff8000"),
+            Some(Color::from_rgba(255, 128, 0, 255))
+        );
+        assert_eq!(
+            hex_color::parse("#00000080"),
+            Some(Color::from_rgba(0, 0, 0, 128))
+        );
+        assert_eq!(hex_color::parse("ff8000"), None);
+        assert_eq!(hex_color::parse("#ff80"), None);
+        assert_eq!(hex_color::parse("#gg0000"), None);
+    }
+
+    #[test]
+    fn partial_file_uses_classic_colors() {
+        let theme: Theme =
+            serde_json::from_str(r##"{"ball": "#ff0000", "court_lines": true}"##).unwrap();
+        assert_eq!(theme.ball, Color::from_rgba(255, 0, 0, 255));
+        assert!(theme.court_lines);
+        assert_eq!(theme.paddles, Theme::classic().paddles);
+    }
+
+    #[test]
+    fn theme_file_round_trip() {
+        for name in Theme::BUILTIN {
+            let theme = Theme::builtin(name).unwrap();
+            let json = serde_json::to_string(&theme).unwrap();
+            assert_eq!(serde_json::from_str::<Theme>(&json).unwrap(), theme);
+        }
+    }
+
+    #[test]
+    fn unknown_theme_is_classic() {
+        assert_eq!(Theme::load("does-not-exist.json"), Theme::classic());
+    }
+}
@@ -1,7 +1,16 @@
+use crate::{helpers::StateHasher, render::CommandList, theme::Theme};
+
 pub trait Movable {
     fn perform_movement(&mut self);
 }
 
+pub trait StateHash {
+    /// Write the fields of the object into `hasher` one by one, in declaration order and at fixed widths,
+    /// so the checksums do not depend on the compiler or the platform
+    fn hash_state(&self, hasher: &mut StateHasher);
+}
+
 pub trait Drawable {
-    fn draw(&self, x: f32, y: f32);
+    /// Record the drawing of the object at (x, y) into `commands`, with the colors of `theme`
+    fn draw(&self, commands: &mut CommandList, theme: &Theme, x: f32, y: f32);
 }
@@ -0,0 +1,149 @@
+//! Screenshot tests: screens are drawn with the software renderer and compared to the PNG images in `tests/golden`.
+//! Run with `UPDATE_GOLDEN=1` to write the images of new screens, or to overwrite them after an intended change.
+
+use std::{env, fs, path::Path};
+
+use good_game_rusty_pong::{
+    game_state::{GameState, Side},
+    interpolation::RenderPositions,
+    lobby::Lobby,
+    main_menu::MainMenu,
+    match_rules::MatchPreset,
+    playfield::{draw_court, draw_names, draw_playfield, draw_results},
+    render::CommandList,
+    software_renderer::{RgbaImage, SoftwareRenderer},
+    theme::Theme,
+    DEFAULT_PLAYER_NAME, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+use macroquad::math::vec2;
+
+/// Draw `commands` on a `width` by `height` image and compare it to the golden image `name`
+fn assert_matches_golden(name: &str, commands: &CommandList, width: u32, height: u32) {
+    let mut renderer = SoftwareRenderer::new(width, height);
+    renderer.execute(commands);
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{name}.png"));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).expect("Could not create the golden directory.");
+        renderer
+            .save_png(&path)
+            .expect("Could not write the golden image.");
+        return;
+    }
+    if !path.exists() {
+        panic!(
+            "{} is missing, run with UPDATE_GOLDEN=1 to write it",
+            path.display()
+        );
+    }
+
+    let expected = fs::read(&path).expect("Could not read the golden image.");
+    let expected = RgbaImage::decode_png(&expected).expect("Invalid golden image.");
+    if &expected != renderer.image() {
+        let actual = path.with_extension("actual.png");
+        renderer
+            .save_png(&actual)
+            .expect("Could not write the rendered image.");
+        panic!(
+            "{name} does not match {}, the rendered image was written to {}",
+            path.display(),
+            actual.display()
+        );
+    }
+}
+
+fn assert_playfield_matches_golden(name: &str, commands: &CommandList) {
+    assert_matches_golden(name, commands, SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32);
+}
+
+/// Record the court and the game at `state` with `theme`
+fn themed_playfield(state: &GameState, theme: &Theme) -> CommandList {
+    let mut commands = CommandList::new();
+    draw_court(theme, &mut commands);
+    draw_playfield(state, RenderPositions::of(state), theme, &mut commands);
+    commands
+}
+
+#[test]
+fn playfield_at_kickoff() {
+    let commands = themed_playfield(&GameState::new(), &Theme::classic());
+    assert_playfield_matches_golden("playfield_kickoff", &commands);
+}
+
+#[test]
+fn builtin_themes() {
+    for name in Theme::BUILTIN {
+        let theme = Theme::builtin(name).unwrap();
+        let commands = themed_playfield(&GameState::new(), &theme);
+        assert_playfield_matches_golden(&format!("theme_{name}"), &commands);
+    }
+}
+
+#[test]
+fn playfield_with_scores_and_sets() {
+    let mut state = GameState::for_match(MatchPreset::TableTennis.rules(), 0);
+    state.left_paddle.score = 7;
+    state.left_paddle.pos = 120;
+    state.right_paddle.score = 10;
+    state.right_paddle.pos = 200;
+    state.ball.pos_x = 300;
+    state.ball.pos_y = 80;
+    state.sets = [1, 2];
+    let commands = themed_playfield(&state, &Theme::classic());
+    assert_playfield_matches_golden("playfield_sets", &commands);
+}
+
+#[test]
+fn playfield_with_names_after_switching_sides() {
+    let mut state = GameState::for_match(MatchPreset::TableTennis.rules(), 0);
+    state.sides_switched = true;
+    state.sets = [1, 0];
+    let mut commands = themed_playfield(&state, &Theme::classic());
+    let names = ["Ari".to_owned(), "Bea".to_owned()];
+    draw_names(&state, &names, Some(1), &Theme::classic(), &mut commands);
+    assert_playfield_matches_golden("playfield_names", &commands);
+}
+
+#[test]
+fn results_with_rematch_request() {
+    let mut state = GameState::new();
+    state.left_paddle.score = 5;
+    state.right_paddle.score = 3;
+    state.winner = Some(Side::Left);
+    state.rematch_votes = [false, true];
+    let mut commands = CommandList::new();
+    draw_results(
+        &state,
+        Side::Left,
+        Some(0),
+        &Theme::classic(),
+        &mut commands,
+    );
+    assert_playfield_matches_golden("results_rematch", &commands);
+}
+
+#[test]
+fn lobby() {
+    let logo = RgbaImage::decode_png(include_bytes!("../assets/logo.png")).unwrap();
+    let lobby = Lobby::new(
+        vec2(logo.width as f32, logo.height as f32),
+        DEFAULT_PLAYER_NAME,
+    );
+    let (width, height) = (SCREEN_WIDTH * 2.0, SCREEN_HEIGHT * 2.0);
+    let mut commands = CommandList::new();
+    lobby.draw(&mut commands, width);
+    assert_matches_golden("lobby", &commands, width as u32, height as u32);
+}
+
+#[test]
+fn main_menu() {
+    let logo = RgbaImage::decode_png(include_bytes!("../assets/logo.png")).unwrap();
+    let menu = MainMenu::new(vec2(logo.width as f32, logo.height as f32));
+    let (width, height) = (SCREEN_WIDTH * 2.0, SCREEN_HEIGHT * 2.0);
+    let mut commands = CommandList::new();
+    menu.draw(&mut commands, width);
+    assert_matches_golden("main_menu", &commands, width as u32, height as u32);
+}